@@ -0,0 +1,498 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{nfa::NFAError, parser};
+
+/// the syntax tree of a pattern, produced by the parser and compiled by `NFA::from_ast`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ast {
+    /// matches the empty string, like the pattern `` or a branch of `a|`
+    Empty,
+    /// a single character, like `a`
+    Literal(char),
+    /// any single character, `.`,
+    /// `\n` is only matched if `new_line` is set, like `(?s).`
+    Any { new_line: bool },
+    /// a set of characters, like `\w` or `[a-z_]`
+    Class(Class),
+    /// a position matching the empty string, like `^`
+    Assertion(Assertion),
+    /// the sub-patterns one after another, like `abc`
+    Concat(Vec<Ast>),
+    /// any one of the sub-patterns, like `a|b|c`
+    Alternation(Vec<Ast>),
+    /// the sub-pattern repeated at least `min` times and at most `max` times,
+    /// `max` of `None` means unbounded, like `a*`, `a{2,3}`
+    Repeat {
+        ast: Box<Ast>,
+        min: usize,
+        max: Option<usize>,
+    },
+    /// a parenthesized sub-pattern capturing its span, like `(ab)`
+    Group(Box<Ast>),
+    /// a group which can be found by its name as well, like `(?<name>ab)`
+    NamedGroup { name: String, ast: Box<Ast> },
+}
+
+/// the flags changing how a pattern is parsed,
+/// set inline like `(?s)` for the rest of the group, or `(?s:...)` for the inner group
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Flags {
+    /// `s`, `.` matches `\n` as well
+    pub dot_matches_new_line: bool,
+    /// `m`, `^` and `$` match at the start and end of each line, instead of the whole text
+    pub multi_line: bool,
+    /// `u`, `\b` and `\B` take the Unicode letters and digits as word characters as well
+    pub unicode_word: bool,
+}
+
+/// a zero-width assertion about the characters around a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Assertion {
+    /// `\A`, or `^` without the flag `m`
+    StartText,
+    /// `\z`, or `$` without the flag `m`
+    EndText,
+    /// `^` with the flag `m`, at the start of the text or after `\n`
+    StartLine,
+    /// `$` with the flag `m`, at the end of the text or before `\n`
+    EndLine,
+    /// `\b`, between a word character and a non-word character or an edge of the text,
+    /// the word characters are `[0-9A-Za-z_]`, or the Unicode ones as well if `unicode` is set
+    WordBoundary { unicode: bool },
+    /// `\B`, anywhere `\b` does not match
+    NotWordBoundary { unicode: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Class {
+    /// escaped class, like `\w`
+    Perl(PerlClass),
+    /// bracketed class, matching any of its items, like `[a-z_]`,
+    /// or any character except its items if negated, like `[^a-z_]`
+    Bracket {
+        negated: bool,
+        items: Vec<ClassItem>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerlClass {
+    /// `\w`, equal to `[a-zA-Z0-9_]`
+    Word,
+    /// `\W`, equal to `[^a-zA-Z0-9_]`
+    NotWord,
+    /// `\d`, equal to `[0-9]`
+    Digit,
+    /// `\D`, equal to `[^0-9]`
+    NotDigit,
+    /// `\s`, equal to `[ \t\n\x0B\x0C\r]`
+    Space,
+    /// `\S`, equal to `[^ \t\n\x0B\x0C\r]`
+    NotSpace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassItem {
+    /// a single character, like `_`
+    Char(char),
+    /// the characters from the first to the second inclusively, like `a-z`
+    Range(char, char),
+    /// escaped class inside brackets, like `\d`
+    Perl(PerlClass),
+}
+
+/// the characters must be escaped to be written in a pattern literally
+const META_CHARS: [char; 14] = [
+    '\\', '.', '+', '*', '?', '(', ')', '|', '[', ']', '{', '}', '^', '$',
+];
+
+/// the characters must be escaped to be written inside brackets literally
+const CLASS_META_CHARS: [char; 5] = ['\\', '[', ']', '^', '-'];
+
+/// writes the character escaped if it's in `meta_chars` or a control character
+fn write_char(f: &mut std::fmt::Formatter<'_>, c: char, meta_chars: &[char]) -> std::fmt::Result {
+    match c {
+        '\n' => write!(f, "\\n"),
+        '\t' => write!(f, "\\t"),
+        '\r' => write!(f, "\\r"),
+        c if c.is_control() && (c as u32) <= 0xFF => write!(f, "\\x{:02X}", c as u32),
+        c if c.is_control() => write!(f, "\\u{{{:X}}}", c as u32),
+        c if meta_chars.contains(&c) => write!(f, "\\{}", c),
+        c => write!(f, "{}", c),
+    }
+}
+
+impl FromStr for Ast {
+    type Err = NFAError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parser::parse(s, Flags::default())
+    }
+}
+
+impl Ast {
+    /// parses the pattern with the flags set in advance, inline flags of the pattern still apply
+    pub fn parse_with(pattern: &str, flags: Flags) -> Result<Self, NFAError> {
+        parser::parse(pattern, flags)
+    }
+
+    /// the count of atoms once the counted repetitions are expanded,
+    /// an unbounded repetition counts its least, like `a{2,}` has 2
+    pub fn expanded_len(&self) -> usize {
+        match self {
+            Ast::Concat(asts) | Ast::Alternation(asts) => asts
+                .iter()
+                .map(Ast::expanded_len)
+                .fold(0, usize::saturating_add),
+            Ast::Repeat { ast, min, max } => ast
+                .expanded_len()
+                .saturating_mul(max.unwrap_or(*min).max(1)),
+            Ast::Group(ast) | Ast::NamedGroup { ast, .. } => ast.expanded_len(),
+            _ => 1,
+        }
+    }
+
+    /// whether the ast must be parenthesized when it's the operand of a repetition
+    fn needs_group_in_repeat(&self) -> bool {
+        matches!(self, Ast::Empty | Ast::Concat(_) | Ast::Alternation(_))
+    }
+}
+
+/// prints the ast back as a canonical pattern,
+/// sub-patterns are put in non-capturing groups where the precedence needs
+impl Display for Ast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ast::Empty => Ok(()),
+            Ast::Literal(c) => write_char(f, *c, &META_CHARS),
+            Ast::Any { new_line: false } => write!(f, "."),
+            Ast::Any { new_line: true } => write!(f, "(?s:.)"),
+            Ast::Class(class) => write!(f, "{}", class),
+            Ast::Assertion(assertion) => write!(f, "{}", assertion),
+            Ast::Concat(asts) => {
+                for ast in asts {
+                    match ast {
+                        Ast::Alternation(_) => write!(f, "(?:{})", ast)?,
+                        _ => write!(f, "{}", ast)?,
+                    }
+                }
+                Ok(())
+            }
+            Ast::Alternation(asts) => {
+                for (i, ast) in asts.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "|")?;
+                    }
+                    write!(f, "{}", ast)?;
+                }
+                Ok(())
+            }
+            Ast::Repeat { ast, min, max } => {
+                if ast.needs_group_in_repeat() {
+                    write!(f, "(?:{})", ast)?;
+                } else {
+                    write!(f, "{}", ast)?;
+                }
+
+                match (min, max) {
+                    (0, None) => write!(f, "*"),
+                    (1, None) => write!(f, "+"),
+                    (0, Some(1)) => write!(f, "?"),
+                    (min, None) => write!(f, "{{{},}}", min),
+                    (min, Some(max)) if min == max => write!(f, "{{{}}}", min),
+                    (min, Some(max)) => write!(f, "{{{},{}}}", min, max),
+                }
+            }
+            Ast::Group(ast) => write!(f, "({})", ast),
+            Ast::NamedGroup { name, ast } => write!(f, "(?<{}>{})", name, ast),
+        }
+    }
+}
+
+impl Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Class::Perl(perl) => write!(f, "{}", perl),
+            Class::Bracket { negated, items } => {
+                write!(f, "[")?;
+                if *negated {
+                    write!(f, "^")?;
+                }
+                for item in items {
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// the assertions are printed the same with or without the flag `m`
+impl Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Assertion::StartText => write!(f, "\\A"),
+            Assertion::EndText => write!(f, "\\z"),
+            Assertion::StartLine => write!(f, "(?m:^)"),
+            Assertion::EndLine => write!(f, "(?m:$)"),
+            Assertion::WordBoundary { unicode: false } => write!(f, "\\b"),
+            Assertion::WordBoundary { unicode: true } => write!(f, "(?u:\\b)"),
+            Assertion::NotWordBoundary { unicode: false } => write!(f, "\\B"),
+            Assertion::NotWordBoundary { unicode: true } => write!(f, "(?u:\\B)"),
+        }
+    }
+}
+
+impl Display for PerlClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PerlClass::Word => write!(f, "\\w"),
+            PerlClass::NotWord => write!(f, "\\W"),
+            PerlClass::Digit => write!(f, "\\d"),
+            PerlClass::NotDigit => write!(f, "\\D"),
+            PerlClass::Space => write!(f, "\\s"),
+            PerlClass::NotSpace => write!(f, "\\S"),
+        }
+    }
+}
+
+impl Display for ClassItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClassItem::Char(c) => write_char(f, *c, &CLASS_META_CHARS),
+            ClassItem::Range(from, to) => {
+                write_char(f, *from, &CLASS_META_CHARS)?;
+                write!(f, "-")?;
+                write_char(f, *to, &CLASS_META_CHARS)
+            }
+            ClassItem::Perl(perl) => write!(f, "{}", perl),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_literals() {
+        let ast = Ast::from_str("ab").unwrap();
+
+        assert_eq!(ast, Ast::Concat(vec![Ast::Literal('a'), Ast::Literal('b')]));
+    }
+
+    #[test]
+    fn parse_empty() {
+        assert_eq!(Ast::from_str("").unwrap(), Ast::Empty);
+        assert_eq!(
+            Ast::from_str("a|").unwrap(),
+            Ast::Alternation(vec![Ast::Literal('a'), Ast::Empty])
+        );
+    }
+
+    #[test]
+    fn parse_precedence() {
+        let ast = Ast::from_str("ab*|(c)").unwrap();
+
+        assert_eq!(
+            ast,
+            Ast::Alternation(vec![
+                Ast::Concat(vec![
+                    Ast::Literal('a'),
+                    Ast::Repeat {
+                        ast: Box::new(Ast::Literal('b')),
+                        min: 0,
+                        max: None
+                    }
+                ]),
+                Ast::Group(Box::new(Ast::Literal('c'))),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_class() {
+        let ast = Ast::from_str(r"[a-zA_\d]\w").unwrap();
+
+        assert_eq!(
+            ast,
+            Ast::Concat(vec![
+                Ast::Class(Class::Bracket {
+                    negated: false,
+                    items: vec![
+                        ClassItem::Range('a', 'z'),
+                        ClassItem::Char('A'),
+                        ClassItem::Char('_'),
+                        ClassItem::Perl(PerlClass::Digit),
+                    ]
+                }),
+                Ast::Class(Class::Perl(PerlClass::Word)),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_negated_class() {
+        let ast = Ast::from_str(r"[^]α-ω\\-]").unwrap();
+
+        assert_eq!(
+            ast,
+            Ast::Class(Class::Bracket {
+                negated: true,
+                items: vec![
+                    ClassItem::Char(']'),
+                    ClassItem::Range('α', 'ω'),
+                    ClassItem::Char('\\'),
+                    ClassItem::Char('-'),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn parse_flags() {
+        let ast = Ast::from_str("(?s).(?-s).(?s:.)(.)").unwrap();
+
+        assert_eq!(
+            ast,
+            Ast::Concat(vec![
+                Ast::Any { new_line: true },
+                Ast::Any { new_line: false },
+                Ast::Any { new_line: true },
+                Ast::Group(Box::new(Ast::Any { new_line: false })),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_with_flags() {
+        let flags = Flags {
+            dot_matches_new_line: true,
+            ..Flags::default()
+        };
+
+        assert_eq!(
+            Ast::parse_with("(.)|.", flags).unwrap(),
+            Ast::Alternation(vec![
+                Ast::Group(Box::new(Ast::Any { new_line: true })),
+                Ast::Any { new_line: true },
+            ])
+        );
+        assert_eq!(
+            Ast::parse_with("(?-s).", flags).unwrap(),
+            Ast::Any { new_line: false }
+        );
+    }
+
+    #[test]
+    fn parse_groups() {
+        let ast = Ast::from_str("(?<int>a)(?:b)(?P<frac>c)").unwrap();
+
+        assert_eq!(
+            ast,
+            Ast::Concat(vec![
+                Ast::NamedGroup {
+                    name: "int".to_string(),
+                    ast: Box::new(Ast::Literal('a'))
+                },
+                Ast::Literal('b'),
+                Ast::NamedGroup {
+                    name: "frac".to_string(),
+                    ast: Box::new(Ast::Literal('c'))
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_assertions() {
+        let ast = Ast::from_str(r"^\A(?m)^$\z(?-m)$").unwrap();
+
+        assert_eq!(
+            ast,
+            Ast::Concat(vec![
+                Ast::Assertion(Assertion::StartText),
+                Ast::Assertion(Assertion::StartText),
+                Ast::Assertion(Assertion::StartLine),
+                Ast::Assertion(Assertion::EndLine),
+                Ast::Assertion(Assertion::EndText),
+                Ast::Assertion(Assertion::EndText),
+            ])
+        );
+
+        let ast = Ast::from_str(r"\b\B(?u)\b[\w]").unwrap();
+
+        assert_eq!(
+            ast,
+            Ast::Concat(vec![
+                Ast::Assertion(Assertion::WordBoundary { unicode: false }),
+                Ast::Assertion(Assertion::NotWordBoundary { unicode: false }),
+                Ast::Assertion(Assertion::WordBoundary { unicode: true }),
+                Ast::Class(Class::Bracket {
+                    negated: false,
+                    items: vec![ClassItem::Perl(PerlClass::Word)],
+                }),
+            ])
+        );
+    }
+
+    #[test]
+    fn display_class_escapes() {
+        let ast = Ast::Class(Class::Bracket {
+            negated: false,
+            items: vec![
+                ClassItem::Char('-'),
+                ClassItem::Char('a'),
+                ClassItem::Char(']'),
+            ],
+        });
+
+        assert_eq!(ast.to_string(), r"[\-a\]]");
+    }
+
+    #[test]
+    fn display_canonical_pattern() {
+        const PATTERNS: [&str; 16] = [
+            "abc",
+            "if|else|while",
+            "(ab|cd)+e",
+            r"[a-z0-9\w]{1,3}[\d]{2}x{2,}",
+            "a*b+c?",
+            r"a\\b",
+            "a|",
+            "()*",
+            r"[^\]a-f\-]",
+            r"\d+\.\D*\s\S\W[\s\x01-\x7F]",
+            r"a\n\t\r\x01\$\^\[\{\}",
+            "a.(?s:.)*",
+            r"(?<int>\d+)\.(?<frac_2>\d+)",
+            "a(?:b|c)(?:de)+",
+            r"\Aa(?m:$)(?m:^)b\z",
+            r"\bif\B(?u:\b)(?u:\B)",
+        ];
+
+        for pattern in PATTERNS {
+            let ast = Ast::from_str(pattern).unwrap();
+            assert_eq!(ast.to_string(), pattern);
+        }
+    }
+
+    #[test]
+    fn display_builds_groups_and_escapes() {
+        let ast = Ast::Repeat {
+            ast: Box::new(Ast::Concat(vec![
+                Ast::Literal('*'),
+                Ast::Alternation(vec![Ast::Literal('a'), Ast::Literal('(')]),
+            ])),
+            min: 2,
+            max: Some(4),
+        };
+
+        let pattern = ast.to_string();
+        assert_eq!(pattern, r"(?:\*(?:a|\()){2,4}");
+
+        // the groups printed are non-capturing, they leave no trace in the ast
+        let reparsed = Ast::from_str(&pattern).unwrap();
+        assert_eq!(reparsed, ast);
+    }
+}
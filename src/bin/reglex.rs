@@ -0,0 +1,141 @@
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader},
+    process::ExitCode,
+    str::FromStr,
+};
+
+use reglex::{codegen::generate, dfa::DFA, nfa::NFA, spec::Spec};
+
+const USAGE: &str = "usage:
+    reglex match <pattern> <text>       prints every match of the pattern in the text
+    reglex grep <pattern> [files...]    prints the lines matching the pattern, reads stdin without files
+    reglex gen <spec.l> [-o lexer.rs]   generates the Rust lexer of the lex spec, prints it without -o
+    reglex dot <pattern> [--dfa]        prints the NFA of the pattern in Graphviz DOT, or its DFA";
+
+/// like grep, exits with 0 if anything matches, 1 if nothing does, and 2 on errors
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// # Return
+/// whether anything is matched, always true for the commands not matching
+fn run(args: &[String]) -> Result<bool, String> {
+    match args {
+        [command, pattern, text] if command == "match" => find(pattern, text),
+        [command, pattern, files @ ..] if command == "grep" => grep(pattern, files),
+        [command, spec, rest @ ..] if command == "gen" => match rest {
+            [] => gen(spec, None),
+            [flag, output] if flag == "-o" => gen(spec, Some(output)),
+            _ => Err(USAGE.to_string()),
+        },
+        [command, pattern] if command == "dot" => dot(pattern, false),
+        [command, pattern, flag] if command == "dot" && flag == "--dfa" => dot(pattern, true),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn compile(pattern: &str) -> Result<NFA, String> {
+    NFA::from_str(pattern).map_err(|err| err.render(pattern))
+}
+
+fn find(pattern: &str, text: &str) -> Result<bool, String> {
+    let nfa = compile(pattern)?;
+
+    let mut found = false;
+    for m in nfa.find_iter(text) {
+        println!("{}..{}: {}", m.start(), m.end(), m.as_str());
+        found = true;
+    }
+
+    Ok(found)
+}
+
+fn grep(pattern: &str, files: &[String]) -> Result<bool, String> {
+    let nfa = compile(pattern)?;
+
+    if files.is_empty() {
+        return grep_lines(&nfa, io::stdin().lock(), None);
+    }
+
+    let mut found = false;
+    for path in files {
+        let file = fs::File::open(path).map_err(|err| format!("{}: {}", path, err))?;
+        // the lines are prefixed by the file only if there are several
+        let prefix = (files.len() > 1).then_some(path.as_str());
+        found |= grep_lines(&nfa, BufReader::new(file), prefix)
+            .map_err(|err| format!("{}: {}", path, err))?;
+    }
+
+    Ok(found)
+}
+
+/// the lines which are not valid UTF-8 are matched with their wrong bytes replaced by `U+FFFD`
+fn grep_lines(nfa: &NFA, mut reader: impl BufRead, prefix: Option<&str>) -> Result<bool, String> {
+    let mut found = false;
+    let mut bytes = Vec::new();
+
+    loop {
+        bytes.clear();
+        let read = reader
+            .read_until(b'\n', &mut bytes)
+            .map_err(|err| err.to_string())?;
+        if read == 0 {
+            break;
+        }
+
+        let line = String::from_utf8_lossy(&bytes);
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if nfa.find(line).is_none() {
+            continue;
+        }
+
+        match prefix {
+            Some(prefix) => println!("{}:{}", prefix, line),
+            None => println!("{}", line),
+        }
+        found = true;
+    }
+
+    Ok(found)
+}
+
+fn gen(path: &str, output: Option<&String>) -> Result<bool, String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let lexer = Spec::parse(&source)
+        .and_then(|spec| spec.lexer())
+        .map_err(|err| format!("{}: {}", path, err))?;
+
+    let code = generate(&lexer);
+    match output {
+        Some(output) => fs::write(output, code).map_err(|err| format!("{}: {}", output, err))?,
+        None => print!("{}", code),
+    }
+
+    Ok(true)
+}
+
+fn dot(pattern: &str, dfa: bool) -> Result<bool, String> {
+    let nfa = compile(pattern)?;
+
+    match dfa {
+        true => {
+            let mut dfa = DFA::from(nfa);
+            dfa.minimize();
+            print!("{}", dfa.to_dot());
+        }
+        false => print!("{}", nfa.to_dot()),
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,229 @@
+use std::fmt::{Display, Write};
+
+use crate::lexer::Lexer;
+
+/// generates a Rust module of the lexer, which matches by the tables of its DFA
+/// and depends on nothing but `core`, it's meant to be written into `OUT_DIR` from `build.rs`
+///
+/// the module has:
+/// - `pub static KINDS: [&str; _]`, the token kind of every rule, printed by `Display`
+/// - `pub struct Token<'a> { rule, kind, span, text }`
+/// - `pub fn next_token(input: &str, at: usize) -> Option<Token<'_>>`,
+///   the longest token at the byte offset, the earliest rule if several match it
+///
+/// ```text
+/// // build.rs
+/// let lexer = Spec::parse(&fs::read_to_string("lexer.l")?)?.lexer()?;
+/// fs::write(Path::new(&env::var("OUT_DIR")?).join("lexer.rs"), generate(&lexer))?;
+///
+/// // main.rs
+/// mod lexer {
+///     include!(concat!(env!("OUT_DIR"), "/lexer.rs"));
+/// }
+/// ```
+pub fn generate<K: Display>(lexer: &Lexer<K>) -> String {
+    let dfa = lexer.dfa();
+    let classes = dfa.class_ranges();
+    let status_count = dfa.status_count();
+    let index = |id: Option<usize>| id.map_or("NONE".to_string(), |id| id.to_string());
+
+    let mut code = String::new();
+    // writing into a String never fails
+    macro_rules! emit {
+        ($($arg: tt)*) => {
+            writeln!(code, $($arg)*).unwrap()
+        };
+    }
+
+    emit!("// generated by reglex, do not edit");
+    emit!();
+    emit!("/// sorted ranges of characters, all the characters of a class go to the same status");
+    emit!("static CLASSES: [(char, char); {}] = [", classes.len());
+    for (from, to) in classes {
+        emit!("    ({:?}, {:?}),", from, to);
+    }
+    emit!("];");
+    emit!();
+
+    emit!("/// no status, or no rule");
+    emit!("const NONE: u32 = u32::MAX;");
+    emit!();
+
+    emit!("/// the next status of each status through each class");
+    emit!(
+        "static NEXT: [[u32; {}]; {}] = [",
+        classes.len(),
+        status_count
+    );
+    for status in 0..status_count {
+        let row: Vec<_> = (0..classes.len())
+            .map(|class| index(dfa.next(status, class)))
+            .collect();
+        emit!("    [{}],", row.join(", "));
+    }
+    emit!("];");
+    emit!();
+
+    emit!("/// the rule each status accepts right before a character of each class, and at the end of the input last");
+    emit!(
+        "static ACCEPT: [[u32; {}]; {}] = [",
+        classes.len() + 1,
+        status_count
+    );
+    for status in 0..status_count {
+        let row: Vec<_> = (0..=classes.len())
+            .map(|class| index(dfa.accept(status, class)))
+            .collect();
+        emit!("    [{}],", row.join(", "));
+    }
+    emit!("];");
+    emit!();
+
+    emit!("/// the start after a character of each class, the status 0 is the start at the beginning of the input");
+    let starts: Vec<_> = dfa.starts().iter().map(|start| start.to_string()).collect();
+    emit!(
+        "static STARTS: [u32; {}] = [{}];",
+        starts.len(),
+        starts.join(", ")
+    );
+    emit!();
+
+    emit!("/// the token kind of every rule");
+    emit!("pub static KINDS: [&str; {}] = [", lexer.kinds().len());
+    for kind in lexer.kinds() {
+        emit!("    {:?},", kind.to_string());
+    }
+    emit!("];");
+    emit!();
+
+    code.push_str(RUNTIME);
+    code
+}
+
+/// the part of the generated module which is the same for every lexer
+const RUNTIME: &str = r#"/// a token of the input, the span is in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// the index of the rule
+    pub rule: usize,
+    pub kind: &'static str,
+    pub span: core::ops::Range<usize>,
+    pub text: &'a str,
+}
+
+/// the index of the class containing `c`, the classes cover every character
+fn class_of(c: char) -> usize {
+    CLASSES
+        .binary_search_by(|&(from, to)| {
+            if to < c {
+                core::cmp::Ordering::Less
+            } else if from > c {
+                core::cmp::Ordering::Greater
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        })
+        .unwrap_or(0)
+}
+
+/// the longest token at the byte offset `at`, the earliest rule if several match it,
+/// `None` if no rule matches a non empty text there
+pub fn next_token(input: &str, at: usize) -> Option<Token<'_>> {
+    let mut current = match input[..at].chars().next_back() {
+        Some(c) => STARTS[class_of(c)] as usize,
+        None => 0,
+    };
+    let mut longest = None;
+
+    for (i, c) in input[at..].char_indices() {
+        let class = class_of(c);
+        if ACCEPT[current][class] != NONE {
+            longest = Some((at + i, ACCEPT[current][class]));
+        }
+
+        match NEXT[current][class] {
+            NONE => return token(input, at, longest),
+            next => current = next as usize,
+        }
+    }
+
+    if ACCEPT[current][CLASSES.len()] != NONE {
+        longest = Some((input.len(), ACCEPT[current][CLASSES.len()]));
+    }
+    token(input, at, longest)
+}
+
+fn token(input: &str, at: usize, longest: Option<(usize, u32)>) -> Option<Token<'_>> {
+    let (end, rule) = longest.filter(|&(end, _)| end > at)?;
+
+    Some(Token {
+        rule: rule as usize,
+        kind: KINDS[rule as usize],
+        span: at..end,
+        text: &input[at..end],
+    })
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod generated {
+        include!("testdata/generated_lexer.rs");
+    }
+
+    fn lexer() -> Lexer<&'static str> {
+        Lexer::new([
+            (r"\bif\b", "If"),
+            (r"[a-z]+", "Ident"),
+            (r"[0-9]+", "Number"),
+            (r"==|=", "Op"),
+            (r"\s+", "Space"),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn same_as_the_generated_file() {
+        // regenerate the file by writing `generate(&lexer())` into it if the generator changes
+        assert_eq!(
+            generate(&lexer()),
+            include_str!("testdata/generated_lexer.rs")
+        );
+    }
+
+    #[test]
+    fn generated_lexer_matches_the_same() {
+        let lexer = lexer();
+
+        for input in ["if iffy == 10", "x=elif", "if\n=", "a\u{e9}", "", "=="] {
+            let mut at = 0;
+            let mut tokens = lexer.tokens(input);
+
+            loop {
+                let expected = tokens.next().and_then(Result::ok);
+                let token = generated::next_token(input, at);
+
+                assert_eq!(
+                    token.as_ref().map(|token| (token.kind, token.span.clone())),
+                    expected.map(|token| (token.kind, token.span)),
+                    "{:?} at {}",
+                    input,
+                    at
+                );
+                match token {
+                    Some(token) => at = token.span.end,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn kinds_are_escaped() {
+        let lexer = Lexer::new([("a", "\"quoted\"\n")]).unwrap();
+
+        assert!(generate(&lexer).contains(r#"    "\"quoted\"\n","#));
+    }
+}
@@ -1,117 +1,935 @@
-use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet},
-    rc::Rc,
-};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 
 use crate::{
-    new_id,
-    nfa::{Status, StatusBox, NFA},
-    status_rules::StatusTargetRule,
+    ast::Assertion,
+    dot::quote,
+    nfa::{Look, StateId, Transition, NFA},
+    status_rules::{next_char, prev_char, IntervalSetRule},
 };
 
-type DFAStatusBox = Rc<RefCell<DFAStatus>>;
-
+/// a status of DFA, standing for a set of NFA status and what the assertions see of the character before
+#[derive(Debug, Clone)]
 struct DFAStatus {
-    id: usize,
-    status_set: Vec<StatusBox>,
-    next: Vec<(Box<dyn StatusTargetRule>, DFAStatusBox)>,
+    /// the index of the rule accepted right before a character of each class, and at the end of the text last,
+    /// the earliest rule if several are, an assertion like `$` may hold before some characters only
+    accept: Vec<Option<usize>>,
+    /// the next status for each class of the DFA, `None` if the class leads nowhere
+    next: Vec<Option<usize>>,
 }
 
+/// the DFA built from NFA by the powerset construction,
+/// the status 0 is the start, the others are numbered in the order they are found from it,
+/// so the same rules always give the same numbers
+///
+/// it can be built from several NFA as rules, sharing the start status,
+/// then every accepting status knows which rule it accepts
+///
+/// the characters are split into classes, all the characters of a class always go to the same status,
+/// so a status keeps a transition for each class instead of each character
+#[derive(Debug, Clone)]
 pub struct DFA {
-    start: DFAStatusBox,
+    /// sorted, disjoint ranges covering every character
+    classes: Vec<(char, char)>,
+    status_list: Vec<DFAStatus>,
+    /// the start within the text for each class of the character before,
+    /// they differ from the status 0 only if there are assertions looking at that character
+    starts: Vec<usize>,
 }
 
 impl DFA {
+    /// creates a DFA matching the empty string only
     pub fn new() -> Self {
         Self {
-            start: DFAStatus::boxed(Vec::new(), Vec::new()),
+            classes: vec![('\0', char::MAX)],
+            status_list: vec![DFAStatus {
+                accept: vec![Some(0), Some(0)],
+                next: vec![None],
+            }],
+            starts: vec![0],
+        }
+    }
+
+    pub fn status_count(&self) -> usize {
+        self.status_list.len()
+    }
+
+    /// whether the whole text is matched
+    pub fn is_match(&self, text: &str) -> bool {
+        self.matched_rule(text).is_some()
+    }
+
+    /// the index of the rule matching the whole text, the earliest rule if several do
+    pub fn matched_rule(&self, text: &str) -> Option<usize> {
+        let mut current = 0;
+
+        for c in text.chars() {
+            current = self.status_list[current].next[self.class_of(c)?]?;
+        }
+
+        self.status_list[current].accept[self.classes.len()]
+    }
+
+    /// the longest match starting at `start` of the haystack, the earliest rule if several match it
+    ///
+    /// # Return
+    /// the end of the match and the index of the rule
+    pub fn longest_match(&self, haystack: &str, start: usize) -> Option<(usize, usize)> {
+        let mut current = match haystack[..start].chars().next_back() {
+            Some(c) => self.starts[self.class_of(c)?],
+            None => 0,
+        };
+        let mut longest = None;
+
+        for (i, c) in haystack[start..].char_indices() {
+            let class = self.class_of(c)?;
+            let status = &self.status_list[current];
+            if let Some(rule) = status.accept[class] {
+                longest = Some((start + i, rule));
+            }
+
+            match status.next[class] {
+                Some(next) => current = next,
+                None => return longest,
+            }
+        }
+
+        match self.status_list[current].accept[self.classes.len()] {
+            Some(rule) => Some((haystack.len(), rule)),
+            None => longest,
+        }
+    }
+
+    /// merges the equivalent status by Hopcroft's partition refinement over the classes,
+    /// status accepting different rules are never merged
+    ///
+    /// # Return
+    /// the count of status before and after
+    pub fn minimize(&mut self) -> (usize, usize) {
+        let before = self.status_list.len();
+        // a status standing for `None`, the transitions to nowhere go to it
+        let sink = before;
+        let target = |status: usize, class: usize| match self.status_list.get(status) {
+            Some(status) => status.next[class].unwrap_or(sink),
+            None => sink,
+        };
+
+        // the status going to the status through each class
+        let mut inverse = vec![vec![Vec::new(); before + 1]; self.classes.len()];
+        for status in 0..=sink {
+            for (class, inverse) in inverse.iter_mut().enumerate() {
+                inverse[target(status, class)].push(status);
+            }
+        }
+
+        // split by the accepted rules first, the sink accepts nothing
+        let nothing = vec![None; self.classes.len() + 1];
+        let mut by_accept = BTreeMap::new();
+        for status in 0..=sink {
+            let accept = self
+                .status_list
+                .get(status)
+                .map_or(&nothing, |status| &status.accept);
+            by_accept
+                .entry(accept)
+                .or_insert_with(Vec::new)
+                .push(status);
+        }
+        let mut blocks: Vec<Vec<usize>> = by_accept.into_values().collect();
+        let mut block_of = vec![0; before + 1];
+        for (index, block) in blocks.iter().enumerate() {
+            for &status in block {
+                block_of[status] = index;
+            }
+        }
+
+        let mut worklist: Vec<usize> = (0..blocks.len()).collect();
+        let mut in_worklist = vec![true; blocks.len()];
+
+        while let Some(splitter) = worklist.pop() {
+            in_worklist[splitter] = false;
+            let splitter = blocks[splitter].clone();
+
+            for inverse in inverse.iter() {
+                let comings: BTreeSet<usize> = splitter
+                    .iter()
+                    .flat_map(|&status| inverse[status].iter().copied())
+                    .collect();
+
+                let mut touched = BTreeMap::new();
+                for &status in comings.iter() {
+                    touched
+                        .entry(block_of[status])
+                        .or_insert_with(Vec::new)
+                        .push(status);
+                }
+
+                for (block, inside) in touched {
+                    if inside.len() == blocks[block].len() {
+                        continue;
+                    }
+
+                    let new_block = blocks.len();
+                    blocks[block].retain(|status| !comings.contains(status));
+                    for &status in inside.iter() {
+                        block_of[status] = new_block;
+                    }
+                    blocks.push(inside);
+
+                    let smaller =
+                        if in_worklist[block] || blocks[new_block].len() < blocks[block].len() {
+                            new_block
+                        } else {
+                            block
+                        };
+                    in_worklist.push(false);
+                    in_worklist[smaller] = true;
+                    worklist.push(smaller);
+                }
+            }
+        }
+
+        // numbers the blocks in the order they are reached from the starts, the sink is dropped
+        let mut numbers = HashMap::new();
+        let mut queue = VecDeque::new();
+        for &start in [0].iter().chain(self.starts.iter()) {
+            let count = numbers.len();
+            numbers.entry(block_of[start]).or_insert_with(|| {
+                queue.push_back(block_of[start]);
+                count
+            });
+        }
+        let mut status_list = Vec::new();
+        while let Some(block) = queue.pop_front() {
+            // the sink is the greatest, it's picked only if the start is dead
+            let status = *blocks[block].iter().min().unwrap();
+            let next = (0..self.classes.len())
+                .map(|class| {
+                    let block = block_of[target(status, class)];
+                    if block == block_of[sink] {
+                        return None;
+                    }
+                    let count = numbers.len();
+                    Some(*numbers.entry(block).or_insert_with(|| {
+                        queue.push_back(block);
+                        count
+                    }))
+                })
+                .collect();
+
+            status_list.push(DFAStatus {
+                accept: self
+                    .status_list
+                    .get(status)
+                    .map_or_else(|| nothing.clone(), |status| status.accept.clone()),
+                next,
+            });
+        }
+
+        self.status_list = status_list;
+        self.starts = self
+            .starts
+            .iter()
+            .map(|&start| numbers[&block_of[start]])
+            .collect();
+
+        (before, self.status_list.len())
+    }
+
+    /// sorted, disjoint ranges covering every character
+    pub(crate) fn class_ranges(&self) -> &[(char, char)] {
+        &self.classes
+    }
+
+    /// the start within the text for each class of the character before
+    pub(crate) fn starts(&self) -> &[usize] {
+        &self.starts
+    }
+
+    /// the next status of the status through the class
+    pub(crate) fn next(&self, status: usize, class: usize) -> Option<usize> {
+        self.status_list[status].next[class]
+    }
+
+    /// the rule the status accepts right before a character of the class,
+    /// the class of `classes().len()` stands for the end of the text
+    pub(crate) fn accept(&self, status: usize, class: usize) -> Option<usize> {
+        self.status_list[status].accept[class]
+    }
+
+    /// renders the DFA in Graphviz DOT, the accepting status are double circles labelled with their rule,
+    /// and the transitions are labelled with the characters they accept
+    ///
+    /// ```text
+    /// digraph DFA {
+    ///     rankdir=LR;
+    ///     node [shape=circle];
+    ///     entry [shape=point];
+    ///     entry -> 0;
+    ///     0;
+    ///     1 [shape=doublecircle, label="1\nrule 0"];
+    ///     0 -> 1 [label="[0-9]"];
+    ///     1 -> 1 [label="[0-9]"];
+    /// }
+    /// ```
+    pub fn to_dot(&self) -> String {
+        self.dot_with(|rule| format!("rule {}", rule))
+    }
+
+    /// renders the DFA in Graphviz DOT, `kind` names each rule,
+    /// a status accepting before some characters only is labelled with its earliest rule
+    pub(crate) fn dot_with(&self, kind: impl Fn(usize) -> String) -> String {
+        let mut lines = vec![
+            "digraph DFA {".to_string(),
+            "    rankdir=LR;".to_string(),
+            "    node [shape=circle];".to_string(),
+            "    entry [shape=point];".to_string(),
+            "    entry -> 0;".to_string(),
+        ];
+
+        for (id, status) in self.status_list.iter().enumerate() {
+            match status.accept.iter().flatten().min() {
+                Some(&rule) => lines.push(format!(
+                    "    {} [shape=doublecircle, label={}];",
+                    id,
+                    quote(&format!("{}\n{}", id, kind(rule)))
+                )),
+                None => lines.push(format!("    {};", id)),
+            }
+        }
+
+        for (id, status) in self.status_list.iter().enumerate() {
+            // the classes going to the same status are drawn as one transition
+            let mut targets = BTreeMap::new();
+            for (class, next) in status.next.iter().enumerate() {
+                if let Some(next) = next {
+                    targets
+                        .entry(next)
+                        .or_insert_with(Vec::new)
+                        .push(self.classes[class]);
+                }
+            }
+
+            for (next, ranges) in targets {
+                let label = IntervalSetRule::new(ranges).to_string();
+                lines.push(format!("    {} -> {} [label={}];", id, next, quote(&label)));
+            }
+        }
+
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    /// the index of the class containing `c`
+    fn class_of(&self, c: char) -> Option<usize> {
+        self.classes
+            .binary_search_by(|&(from, to)| {
+                if to < c {
+                    std::cmp::Ordering::Less
+                } else if from > c {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .ok()
+    }
+
+    /// splits every character into classes,
+    /// a class is within or without each range of every transition,
+    /// and tells apart the characters the assertions see differently if there are assertions,
+    /// the Unicode word characters only if `unicode_words` is set
+    fn classes(
+        nfa: &NFA,
+        status_list: &[StateId],
+        has_assertions: bool,
+        unicode_words: bool,
+    ) -> Vec<(char, char)> {
+        // the same class is often on many transitions, like `a` of `(a|ab)*`, it's counted once
+        let mut rules = BTreeSet::new();
+        for &status in status_list {
+            let throughable = nfa.state(status).transitions.iter();
+            rules.extend(throughable.filter_map(|(transition, _)| transition.rule()));
         }
+        let mut ranges: Vec<_> = rules
+            .into_iter()
+            .flat_map(|rule| rule.intervals())
+            .collect();
+        if has_assertions {
+            ranges.extend(Look::ranges(unicode_words));
+        }
+
+        // every range starts a class, and the character after it starts another
+        let mut bounds: Vec<char> = ranges
+            .into_iter()
+            .flat_map(|(from, to)| [Some(from), next_char(to)])
+            .flatten()
+            .chain(['\0'])
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        bounds
+            .iter()
+            .enumerate()
+            .map(|(i, &from)| {
+                let to = match bounds.get(i + 1) {
+                    Some(&next) => prev_char(next).unwrap(),
+                    None => char::MAX,
+                };
+                (from, to)
+            })
+            .collect()
+    }
+
+    /// every status reachable from the start
+    fn reachable(nfa: &NFA, start: StateId) -> Vec<StateId> {
+        let mut res = Vec::new();
+        let mut visited = vec![false; nfa.state_count()];
+        let mut stack = vec![start];
+
+        while let Some(status) = stack.pop() {
+            if std::mem::replace(&mut visited[status], true) {
+                continue;
+            }
+            let transitions = nfa.state(status).transitions.iter();
+            stack.extend(transitions.map(|(_, next)| *next));
+
+            res.push(status);
+        }
+
+        res
     }
 }
 
-impl DFAStatus {
-    pub fn boxed(
-        status_set: Vec<StatusBox>,
-        next: Vec<(Box<dyn StatusTargetRule>, DFAStatusBox)>,
-    ) -> DFAStatusBox {
-        Rc::new(RefCell::new(Self::new(status_set, next)))
+impl Default for DFA {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn new(
-        status_set: Vec<StatusBox>,
-        next: Vec<(Box<dyn StatusTargetRule>, DFAStatusBox)>,
-    ) -> Self {
-        Self {
-            id: new_id(),
-            status_set,
-            next,
+/// the ids of the NFA status set, sorted and deduplicated, identifying a DFA status
+fn set_key(status_set: &mut Vec<StateId>) -> Vec<StateId> {
+    status_set.sort_unstable();
+    status_set.dedup();
+
+    status_set.clone()
+}
+
+/// the status reachable through the empty transitions,
+/// and the transitions of the assertions holding between `before` and `after`
+fn closure(nfa: &NFA, status_set: &[StateId], before: Look, after: Look) -> Vec<StateId> {
+    let mut res = Vec::new();
+    let mut visited = vec![false; nfa.state_count()];
+    let mut stack = status_set.to_vec();
+
+    while let Some(status) = stack.pop() {
+        if std::mem::replace(&mut visited[status], true) {
+            continue;
+        }
+
+        for (transition, next) in nfa.state(status).transitions.iter() {
+            if transition.is_passed(before, after) {
+                stack.push(*next);
+            }
         }
+
+        res.push(status);
     }
+
+    res
 }
 
-impl From<Vec<StatusBox>> for DFAStatus {
-    fn from(value: Vec<StatusBox>) -> Self {
-        Self::new(value, vec![])
+impl DFA {
+    /// builds the DFA from the rules sharing a start status,
+    /// `matched_rule` tells the index of the rule in the list
+    pub fn from_rules(rules: Vec<NFA>) -> Self {
+        let (nfa, ends) = NFA::union(rules);
+        let status_list = Self::reachable(&nfa, nfa.get_start());
+        let mut assertions = Vec::new();
+        for &status in status_list.iter() {
+            let transitions = nfa.state(status).transitions.iter();
+            assertions.extend(transitions.filter_map(|(transition, _)| match transition {
+                Transition::Assertion(assertion) => Some(*assertion),
+                _ => None,
+            }));
+        }
+        let has_assertions = !assertions.is_empty();
+        let unicode_words = assertions.iter().any(|assertion| {
+            matches!(
+                assertion,
+                Assertion::WordBoundary { unicode: true }
+                    | Assertion::NotWordBoundary { unicode: true }
+            )
+        });
+        let classes = Self::classes(&nfa, &status_list, has_assertions, unicode_words);
+
+        // the index of the rule ending at each NFA status
+        let mut rule_of = vec![None; nfa.state_count()];
+        for (index, end) in ends.into_iter().enumerate() {
+            rule_of[end] = Some(index);
+        }
+        let accepted = |status_set: &[StateId]| {
+            status_set
+                .iter()
+                .filter_map(|&status| rule_of[status])
+                .min()
+        };
+        // without assertions, every character looks the same, so the status are not split by it
+        // nor by the Unicode word characters without the assertions about them
+        let look = |c: Option<char>| match has_assertions {
+            true => Look {
+                unicode_word: unicode_words && Look::of(c).unicode_word,
+                ..Look::of(c)
+            },
+            false => Look::default(),
+        };
+
+        let mut start = nfa.closure_s(nfa.get_start());
+        let start_key = set_key(&mut start);
+        let mut record = HashMap::from([((start_key.clone(), look(None)), 0)]);
+        let mut buf = vec![(start.clone(), look(None))];
+        let mut status_list = Vec::new();
+
+        // the same start seeing the character before, all of them are the status 0 without assertions
+        let starts = classes
+            .iter()
+            .map(|&(from, _)| {
+                let before = look(Some(from));
+                *record
+                    .entry((start_key.clone(), before))
+                    .or_insert_with(|| {
+                        buf.push((start.clone(), before));
+                        buf.len() - 1
+                    })
+            })
+            .collect();
+
+        // `buf` grows while its status are visited, every new set is pushed at the end
+        while status_list.len() < buf.len() {
+            let (status_set, before) = buf[status_list.len()].clone();
+
+            let mut accept = Vec::with_capacity(classes.len() + 1);
+            let mut next = Vec::with_capacity(classes.len());
+            for &(from, _) in classes.iter() {
+                let after = look(Some(from));
+                let closed = closure(&nfa, &status_set, before, after);
+                accept.push(accepted(&closed));
+
+                let mut next_set = nfa.closure_t(nfa.closure_t_a(&closed, from));
+                if next_set.is_empty() {
+                    next.push(None);
+                    continue;
+                }
+
+                let key = (set_key(&mut next_set), after);
+                let index = *record.entry(key).or_insert_with(|| {
+                    buf.push((next_set, after));
+                    buf.len() - 1
+                });
+                next.push(Some(index));
+            }
+            accept.push(accepted(&closure(&nfa, &status_set, before, look(None))));
+
+            status_list.push(DFAStatus { accept, next });
+        }
+
+        Self {
+            classes,
+            status_list,
+            starts,
+        }
     }
 }
 
 impl From<NFA> for DFA {
     fn from(value: NFA) -> Self {
-        let dfa = NFA::new();
-        let mut status = value.get_start();
-        let mut record = HashSet::new();
+        Self::from_rules(vec![value])
+    }
+}
 
-        let mut buf: Vec<DFAStatus> = vec![vec![status].into()];
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
 
-        while let Some(item) = buf.first() {
-            if let None = record.get(&item.id) {
-                continue;
+    use crate::{nfa::NFABuilder, nfa_executor::NFAExecutor};
+
+    use super::*;
+
+    fn dfa(pattern: &str) -> DFA {
+        NFA::from_str(pattern).unwrap().into()
+    }
+
+    #[test]
+    fn empty() {
+        assert!(DFA::new().is_match(""));
+        assert!(!DFA::new().is_match("a"));
+
+        let dfa = dfa("");
+        assert!(dfa.is_match(""));
+        assert!(!dfa.is_match("a"));
+
+        for haystack in ["", "a", "ab"] {
+            assert_eq!(
+                DFA::new().longest_match(haystack, 0),
+                dfa.longest_match(haystack, 0),
+                "{:?}",
+                haystack
+            );
+        }
+        assert_eq!(DFA::new().longest_match("a", 0), Some((0, 0)));
+    }
+
+    #[test]
+    fn literal() {
+        let dfa = dfa("abc");
+
+        assert!(dfa.is_match("abc"));
+        assert!(!dfa.is_match("ab"));
+        assert!(!dfa.is_match("abcd"));
+        assert!(!dfa.is_match("abd"));
+        assert_eq!(dfa.status_count(), 4);
+    }
+
+    #[test]
+    fn classes_split_overlapping_ranges() {
+        let dfa = dfa("[a-m]x|[h-z]y");
+
+        assert_eq!(
+            dfa.classes,
+            [
+                ('\0', '`'),
+                ('a', 'g'),
+                ('h', 'm'),
+                ('n', 'w'),
+                ('x', 'x'),
+                ('y', 'y'),
+                ('z', 'z'),
+                ('{', char::MAX)
+            ]
+        );
+        assert!(dfa.is_match("hx"));
+        assert!(dfa.is_match("hy"));
+        assert!(!dfa.is_match("ay"));
+        assert!(!dfa.is_match("zx"));
+    }
+
+    #[test]
+    fn backtracking_patterns() {
+        const CASES: [(&str, &str, bool); 12] = [
+            ("(ab|ac)d", "acd", true),
+            ("a*a", "aaa", true),
+            ("a*a", "", false),
+            (".*c", "abcabc", true),
+            (".*c", "abcab", false),
+            ("(a|b)*abb", "babaabb", true),
+            ("(a|b)*abb", "babaab", false),
+            (r"\w+@\w+\.com", "me@mail.com", true),
+            ("a{2,3}a", "aaaa", true),
+            ("a{2,3}a", "aaaaa", false),
+            (r"[^\n]*", "a b\tc", true),
+            (r"[^\n]*", "a\nb", false),
+        ];
+
+        for (pattern, text, expected) in CASES {
+            assert_eq!(dfa(pattern).is_match(text), expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn unicode() {
+        let dfa = dfa(r"[α-ω]+\W一");
+
+        assert!(dfa.is_match("αβγ 一"));
+        assert!(!dfa.is_match("αβγ_一"));
+    }
+
+    #[test]
+    fn dot_matches_new_line() {
+        assert!(!dfa("a.c").is_match("a\nc"));
+
+        let nfa = NFABuilder::new()
+            .dot_matches_new_line(true)
+            .build("a.c")
+            .unwrap();
+        assert!(DFA::from(nfa).is_match("a\nc"));
+    }
+
+    #[test]
+    fn same_as_nfa_executor() {
+        const CASES: [(&str, &str); 12] = [
+            ("abc", "abc"),
+            ("abc", "abd"),
+            ("ab*c", "abbbc"),
+            ("ab*c", "ac"),
+            ("ab+c", "ac"),
+            ("if|else|while", "else"),
+            ("(ab|cd)+e", "abcdabe"),
+            ("(ab|cd)+e", "abce"),
+            (r"\d+\.\d+", "3.14"),
+            (r"\d+\.\d+", "3x14"),
+            ("[a-f0-9]{2,4}", "0fa"),
+            ("[^0-9]x", "5x"),
+        ];
+
+        for (pattern, text) in CASES {
+            let nfa = NFA::from_str(pattern).unwrap();
+            let expected = NFAExecutor::new(nfa.clone(), text).test();
+            assert_eq!(DFA::from(nfa).is_match(text), expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn status_are_numbered_the_same() {
+        let build = || {
+            let mut dfa = dfa("(a|b)*abb|[0-9]+");
+            dfa.minimize();
+            dfa
+        };
+        let (dfa, again) = (build(), build());
+
+        assert_eq!(dfa.classes, again.classes);
+        for (status, same) in dfa.status_list.iter().zip(again.status_list.iter()) {
+            assert_eq!(status.next, same.next);
+            assert_eq!(status.accept, same.accept);
+        }
+        assert!(dfa
+            .status_list
+            .iter()
+            .flat_map(|status| status.next.iter().flatten())
+            .all(|&next| next < dfa.status_count()));
+    }
+
+    #[test]
+    fn to_dot() {
+        let mut dfa = DFA::from(NFA::from_str("[0-9]+|x").unwrap());
+        dfa.minimize();
+
+        assert_eq!(
+            dfa.to_dot(),
+            r#"digraph DFA {
+    rankdir=LR;
+    node [shape=circle];
+    entry [shape=point];
+    entry -> 0;
+    0;
+    1 [shape=doublecircle, label="1\nrule 0"];
+    2 [shape=doublecircle, label="2\nrule 0"];
+    0 -> 1 [label="[0-9]"];
+    0 -> 2 [label="x"];
+    1 -> 1 [label="[0-9]"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn longest_match() {
+        let rules = ["if", "[a-z]+", "[0-9]+", " "]
+            .map(|rule| NFA::from_str(rule).unwrap())
+            .to_vec();
+        let mut dfa = DFA::from_rules(rules);
+
+        for _ in 0..2 {
+            assert_eq!(dfa.longest_match("if x", 0), Some((2, 0)));
+            assert_eq!(dfa.longest_match("iffy 1", 0), Some((4, 1)));
+            assert_eq!(dfa.longest_match("iffy 12", 5), Some((7, 2)));
+            assert_eq!(dfa.longest_match("iffy 12", 4), Some((5, 3)));
+            assert_eq!(dfa.longest_match("iffy!", 4), None);
+            dfa.minimize();
+        }
+    }
+
+    #[test]
+    fn longest_match_sees_the_character_before() {
+        let rules = [r"\bif\b", "[a-z]+", r"(?m)^#"]
+            .map(|rule| NFA::from_str(rule).unwrap())
+            .to_vec();
+        let mut dfa = DFA::from_rules(rules);
+
+        for _ in 0..2 {
+            assert_eq!(dfa.longest_match("elif", 2), Some((4, 1)));
+            assert_eq!(dfa.longest_match("el if", 3), Some((5, 0)));
+            assert_eq!(dfa.longest_match("a\n#", 2), Some((3, 2)));
+            assert_eq!(dfa.longest_match("a#", 1), None);
+            dfa.minimize();
+        }
+    }
+
+    #[test]
+    fn nul() {
+        const CASES: [(&str, &str, bool); 5] = [
+            (r"a\x00b", "a\0b", true),
+            (r"a\x00b", "ab", false),
+            ("a.b", "a\0b", true),
+            ("[^a]+", "\0\0", true),
+            (r"\W", "\0", true),
+        ];
+
+        for (pattern, text, expected) in CASES {
+            assert_eq!(dfa(pattern).is_match(text), expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn minimize() {
+        let mut dfa = dfa("(a|b)*abb");
+        let (before, after) = dfa.minimize();
+
+        assert_eq!(before, 5);
+        assert_eq!(after, 4);
+        assert_eq!(dfa.status_count(), 4);
+        assert!(dfa.is_match("babaabb"));
+        assert!(!dfa.is_match("babaab"));
+    }
+
+    #[test]
+    fn minimize_merges_branches() {
+        let mut dfa = dfa("ax|bx|cx|dx");
+        let (before, after) = dfa.minimize();
+
+        assert_eq!(before, 9);
+        assert_eq!(after, 3);
+    }
+
+    #[test]
+    fn minimize_matches_the_same() {
+        const PATTERNS: [&str; 6] = [
+            "(ab|ac)d",
+            "a*a",
+            "(a|b)*abb",
+            r"\w+@\w+\.com",
+            "a{2,3}a",
+            "(if|else|while)+",
+        ];
+        const TEXTS: [&str; 10] = [
+            "",
+            "acd",
+            "abd",
+            "aaa",
+            "abb",
+            "aabb",
+            "me@mail.com",
+            "aaaaa",
+            "ifelse",
+            "whilee",
+        ];
+
+        for pattern in PATTERNS {
+            let dfa = dfa(pattern);
+            let mut minimized = DFA::from(NFA::from_str(pattern).unwrap());
+            minimized.minimize();
+
+            for text in TEXTS {
+                assert_eq!(
+                    minimized.is_match(text),
+                    dfa.is_match(text),
+                    "{} {}",
+                    pattern,
+                    text
+                );
             }
-            record.insert(item.id);
+        }
+    }
 
-            let mut next_set: Vec<Rc<RefCell<DFAStatusBox>>> = Vec::new();
+    #[test]
+    fn minimize_matches_nothing() {
+        let mut dfa = dfa(r"a[^\s\S]");
+        let (_, after) = dfa.minimize();
 
-            let targets = {
-                item.status_set
-                    .iter()
-                    .map(|v| {
-                        let temp = v.borrow();
-                        temp.status_set
-                            .iter()
-                            .map(|s| s.0.clone())
-                            .collect::<Vec<_>>()
-                    })
-                    .flatten()
-                    .collect::<Vec<_>>()
-            };
+        assert_eq!(after, 1);
+        assert!(!dfa.is_match(""));
+        assert!(!dfa.is_match("a"));
+    }
 
-            for target in targets {
-                let ta_set = Status::closure_t_a(&item.status_set, target);
-                let ta_set: Vec<_> = ta_set
-                    .iter()
-                    .map(|v| Status::closure_s(&v))
-                    .flatten()
-                    .collect();
+    #[test]
+    fn rules() {
+        let rules = ["if", "[a-z]+", "[0-9]+", "i[a-z]"]
+            .map(|rule| NFA::from_str(rule).unwrap())
+            .to_vec();
+        let mut dfa = DFA::from_rules(rules);
 
-                
+        for minimized in [false, true] {
+            if minimized {
+                dfa.minimize();
             }
+            assert_eq!(dfa.matched_rule("if"), Some(0));
+            assert_eq!(dfa.matched_rule("i"), Some(1));
+            assert_eq!(dfa.matched_rule("in"), Some(1));
+            assert_eq!(dfa.matched_rule("iff"), Some(1));
+            assert_eq!(dfa.matched_rule("42"), Some(2));
+            assert_eq!(dfa.matched_rule("4a"), None);
+        }
+    }
 
-            // for each in item.status_set {
-            //     for target in targets {
-            //         Status::closure_t_a(&each, item.0);
-            //         let list = Status::closure_s(&item.1)
-            //             .iter()
-            //             .map(|v| v.into())
-            //             .collect();
-            //         next_set.extend(list);
-            //     }
+    #[test]
+    fn assertions() {
+        const CASES: [(&str, &str, bool); 10] = [
+            ("^abc$", "abc", true),
+            (r"\Aab\z", "ab", true),
+            ("a^b", "ab", false),
+            ("a$b", "ab", false),
+            ("(?m)a$\n^b", "a\nb", true),
+            ("a$\nb", "a\nb", false),
+            ("a\n^b", "a\nb", false),
+            ("(?m)(a$|b)*\n", "ba\n", true),
+            ("(?m)(a$|b)+\n", "ab\n", false),
+            ("(?m)^$", "", true),
+        ];
 
-            //     // let new_status = DFAStatus::new(set, vec![]);
-            //     // buf.push(new_status);
-            // }
+        for (pattern, text, expected) in CASES {
+            assert_eq!(dfa(pattern).is_match(text), expected, "{}", pattern);
+
+            let nfa = NFA::from_str(pattern).unwrap();
+            assert_eq!(nfa.is_full_match(text), expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn word_boundaries() {
+        const CASES: [(&str, &str, bool); 7] = [
+            (r"\bif\b", "if", true),
+            (r"\w*\bif", "elif", false),
+            (r"\w*\Bif", "elif", true),
+            (r"if\b \bx", "if x", true),
+            (r".\b.", "éa", true),
+            (r"(?u).\b.", "éa", false),
+            (r"(?u).\b.", "é ", true),
+        ];
+
+        for (pattern, text, expected) in CASES {
+            let mut dfa = dfa(pattern);
+            assert_eq!(dfa.is_match(text), expected, "{}", pattern);
+            dfa.minimize();
+            assert_eq!(dfa.is_match(text), expected, "{}", pattern);
+
+            let nfa = NFA::from_str(pattern).unwrap();
+            assert_eq!(nfa.is_full_match(text), expected, "{}", pattern);
         }
+    }
+
+    #[test]
+    fn assertions_minimized() {
+        let mut dfa = dfa("(?m)(^a|b$)+\n");
+        dfa.minimize();
+
+        assert!(dfa.is_match("a\n"));
+        assert!(dfa.is_match("ab\n"));
+        assert!(!dfa.is_match("ba\n"));
+        assert!(!dfa.is_match("aa\n"));
+    }
+
+    #[test]
+    fn assertions_before_characters() {
+        let rules = ["(?m)a$", "a"]
+            .map(|rule| NFA::from_str(rule).unwrap())
+            .to_vec();
+        let dfa = DFA::from_rules(rules);
 
-        unimplemented!()
+        // accepted right before `\n` only, which is the class of `\n`
+        let a = dfa.status_list[0].next[dfa.class_of('a').unwrap()].unwrap();
+        let accept = &dfa.status_list[a].accept;
+        assert_eq!(accept[dfa.class_of('\n').unwrap()], Some(0));
+        assert_eq!(accept[dfa.class_of('b').unwrap()], Some(1));
+        assert_eq!(accept[dfa.classes.len()], Some(0));
     }
 }
@@ -0,0 +1,30 @@
+/// quotes the text as a string of Graphviz DOT, the line breaks are kept as `\n`
+pub(crate) fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_escapes() {
+        assert_eq!(quote("a"), r#""a""#);
+        assert_eq!(quote(r#"[\-"]"#), r#""[\\-\"]""#);
+        assert_eq!(quote("{\n}"), r#""{\n}""#);
+    }
+}
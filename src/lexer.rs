@@ -0,0 +1,302 @@
+use std::{error::Error, fmt::Display, ops::Range, str::FromStr};
+
+use crate::{
+    dfa::DFA,
+    nfa::{NFAError, NFA},
+};
+
+/// splits the text into tokens by an ordered list of rules, like flex,
+/// the longest match wins, and the earliest rule wins among the matches of the same length
+///
+/// ```text
+/// if      -> If
+/// [a-z]+  -> Ident
+///
+/// "iffy if" => Ident "iffy", If "if"
+/// ```
+#[derive(Debug, Clone)]
+pub struct Lexer<K> {
+    /// the minimized DFA of every rule, its rule index is the index into `kinds`
+    dfa: DFA,
+    kinds: Vec<K>,
+}
+
+impl<K: Clone> Lexer<K> {
+    /// builds the lexer from the rules of pattern and token kind, in the order of priority
+    pub fn new<'p>(rules: impl IntoIterator<Item = (&'p str, K)>) -> Result<Self, LexError> {
+        let mut nfa_list = Vec::new();
+        let mut kinds = Vec::new();
+
+        for (rule, (pattern, kind)) in rules.into_iter().enumerate() {
+            let nfa = NFA::from_str(pattern).map_err(|error| LexError::Pattern { rule, error })?;
+            nfa_list.push(nfa);
+            kinds.push(kind);
+        }
+
+        Ok(Self::from_nfa_list(nfa_list, kinds))
+    }
+
+    /// builds the lexer from the compiled rules, each with the kind at the same index
+    ///
+    /// # Panics
+    /// panics if the count of the rules and the kinds are different
+    pub fn from_nfa_list(rules: Vec<NFA>, kinds: Vec<K>) -> Self {
+        assert_eq!(rules.len(), kinds.len(), "every rule needs a token kind");
+
+        let mut dfa = DFA::from_rules(rules);
+        dfa.minimize();
+
+        Self { dfa, kinds }
+    }
+
+    /// the tokens of the haystack in order, it stops after the first error
+    pub fn tokens<'l, 'h>(&'l self, haystack: &'h str) -> Tokens<'l, 'h, K> {
+        Tokens {
+            lexer: self,
+            haystack,
+            at: 0,
+            failed: false,
+        }
+    }
+}
+
+impl<K> Lexer<K> {
+    /// the DFA of every rule, its rule index is the index into `kinds`
+    pub fn dfa(&self) -> &DFA {
+        &self.dfa
+    }
+
+    /// the token kind of every rule in order
+    pub fn kinds(&self) -> &[K] {
+        &self.kinds
+    }
+}
+
+impl<K: Display> Lexer<K> {
+    /// renders the DFA in Graphviz DOT, the accepting status are labelled with their token kind
+    pub fn to_dot(&self) -> String {
+        self.dfa.dot_with(|rule| self.kinds[rule].to_string())
+    }
+}
+
+/// a piece of the haystack matched by a rule, the span is in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'h, K> {
+    pub kind: K,
+    pub span: Range<usize>,
+    pub text: &'h str,
+}
+
+/// the iterator of tokens, see `Lexer::tokens`
+pub struct Tokens<'l, 'h, K> {
+    lexer: &'l Lexer<K>,
+    haystack: &'h str,
+    /// where the next token starts, always on a char boundary
+    at: usize,
+    failed: bool,
+}
+
+impl<'h, K: Clone> Iterator for Tokens<'_, 'h, K> {
+    type Item = Result<Token<'h, K>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.at >= self.haystack.len() {
+            return None;
+        }
+
+        let start = self.at;
+        match self.lexer.dfa.longest_match(self.haystack, start) {
+            // an empty token would never move forward
+            Some((end, rule)) if end > start => {
+                self.at = end;
+                Some(Ok(Token {
+                    kind: self.lexer.kinds[rule].clone(),
+                    span: start..end,
+                    text: &self.haystack[start..end],
+                }))
+            }
+            _ => {
+                self.failed = true;
+                Some(Err(LexError::NoMatch { offset: start }))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexError {
+    /// the pattern of the rule at the index is wrong
+    Pattern { rule: usize, error: NFAError },
+    /// no rule matches a non empty text at the byte offset
+    NoMatch { offset: usize },
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexError::Pattern { rule, error } => write!(f, "rule {}: {}", rule, error),
+            LexError::NoMatch { offset } => write!(f, "no rule matches at {}", offset),
+        }
+    }
+}
+
+impl Error for LexError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Kind {
+        If,
+        Ident,
+        Number,
+        Eq,
+        Assign,
+        Space,
+    }
+
+    fn lexer() -> Lexer<Kind> {
+        Lexer::new([
+            (r"\bif\b", Kind::If),
+            (r"[a-z]+", Kind::Ident),
+            (r"[0-9]+", Kind::Number),
+            (r"==", Kind::Eq),
+            (r"=", Kind::Assign),
+            (r"\s+", Kind::Space),
+        ])
+        .unwrap()
+    }
+
+    fn kinds<'h>(lexer: &Lexer<Kind>, haystack: &'h str) -> Vec<(Kind, &'h str)> {
+        lexer
+            .tokens(haystack)
+            .map(|token| token.map(|token| (token.kind, token.text)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn longest_match() {
+        let lexer = lexer();
+
+        assert_eq!(
+            kinds(&lexer, "a == 10"),
+            [
+                (Kind::Ident, "a"),
+                (Kind::Space, " "),
+                (Kind::Eq, "=="),
+                (Kind::Space, " "),
+                (Kind::Number, "10"),
+            ]
+        );
+        assert_eq!(
+            kinds(&lexer, "a=1"),
+            [(Kind::Ident, "a"), (Kind::Assign, "="), (Kind::Number, "1")]
+        );
+        assert_eq!(kinds(&lexer, "iffy"), [(Kind::Ident, "iffy")]);
+    }
+
+    #[test]
+    fn earliest_rule_wins_the_tie() {
+        let lexer = lexer();
+
+        assert_eq!(
+            kinds(&lexer, "if x"),
+            [(Kind::If, "if"), (Kind::Space, " "), (Kind::Ident, "x")]
+        );
+
+        let lexer = Lexer::new([("[a-z]+", Kind::Ident), ("if", Kind::If)]).unwrap();
+        assert_eq!(kinds(&lexer, "if"), [(Kind::Ident, "if")]);
+    }
+
+    #[test]
+    fn assertions_see_the_previous_token() {
+        let lexer = Lexer::new([
+            (r"\bif\b", Kind::If),
+            (r"[a-z]", Kind::Ident),
+            (r"=", Kind::Assign),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            kinds(&lexer, "eif"),
+            [(Kind::Ident, "e"), (Kind::Ident, "i"), (Kind::Ident, "f")]
+        );
+        assert_eq!(
+            kinds(&lexer, "e=if"),
+            [(Kind::Ident, "e"), (Kind::Assign, "="), (Kind::If, "if")]
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let lexer = lexer();
+        let tokens: Vec<_> = lexer.tokens("x = é").collect();
+
+        assert_eq!(
+            tokens[..3],
+            [
+                Ok(Token {
+                    kind: Kind::Ident,
+                    span: 0..1,
+                    text: "x"
+                }),
+                Ok(Token {
+                    kind: Kind::Space,
+                    span: 1..2,
+                    text: " "
+                }),
+                Ok(Token {
+                    kind: Kind::Assign,
+                    span: 2..3,
+                    text: "="
+                }),
+            ]
+        );
+        assert_eq!(
+            tokens[3..],
+            [
+                Ok(Token {
+                    kind: Kind::Space,
+                    span: 3..4,
+                    text: " "
+                }),
+                Err(LexError::NoMatch { offset: 4 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_match_is_no_match() {
+        let lexer = Lexer::new([("a*", Kind::Ident)]).unwrap();
+        let tokens: Vec<_> = lexer.tokens("aab").collect();
+
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[1], Err(LexError::NoMatch { offset: 2 }));
+    }
+
+    #[test]
+    fn pattern_error() {
+        let err = Lexer::new([("a", Kind::Ident), ("(b", Kind::If)]).unwrap_err();
+
+        match err {
+            LexError::Pattern { rule, .. } => assert_eq!(rule, 1),
+            err => panic!("unexpected error {}", err),
+        }
+    }
+
+    #[test]
+    fn to_dot_has_the_kinds() {
+        let dot = Lexer::new([("if", "If"), ("[a-z]+", "Ident")])
+            .unwrap()
+            .to_dot();
+
+        assert!(dot.contains(r#"3 [shape=doublecircle, label="3\nIf"];"#));
+        assert!(dot.contains(r#"1 [shape=doublecircle, label="1\nIdent"];"#));
+    }
+
+    #[test]
+    fn empty_haystack() {
+        assert_eq!(lexer().tokens("").count(), 0);
+    }
+}
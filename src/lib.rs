@@ -1,14 +1,11 @@
+pub mod ast;
+pub mod codegen;
 pub mod dfa;
+mod dot;
+pub mod lexer;
 pub mod nfa;
 pub mod nfa_executor;
+mod parser;
+mod pike_vm;
+pub mod spec;
 mod status_rules;
-
-static mut CURRENT_ID: usize = 0;
-
-pub(crate) fn new_id() -> usize {
-    unsafe {
-        CURRENT_ID += 1;
-
-        CURRENT_ID
-    }
-}
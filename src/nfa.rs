@@ -1,35 +1,162 @@
-use crate::{new_id, status_rules::{
-    AlphaLowercaseRule, AlphaRule, AlphaUppercaseRule, AlphanumericUnderlineRule, Digit,
-    SingleCharRule,
-}};
+use crate::{
+    ast::{Assertion, Ast, Class, ClassItem, Flags, PerlClass},
+    dot::quote,
+    status_rules::{
+        next_char, AlphaLowercaseRule, AlphaRule, AlphaUppercaseRule, AlphanumericUnderlineRule,
+        AnyCharRule, Digit, IntervalSetRule, NegatedRule, SingleCharRule, WhitespaceRule,
+    },
+};
 
 use super::status_rules::StatusTargetRule;
-use core::panic;
 use std::{
-    cell::RefCell,
-    iter::Peekable,
-    rc::Rc,
-    str::{Chars, FromStr},
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    ops::Range,
+    str::FromStr,
 };
 
-pub type StatusBox = Rc<RefCell<Status>>;
-
-const EMPTY: char = '\0';
-
+/// the index of a status within the NFA owning it
+pub type StateId = usize;
 
 /// a NodeStatus must be ensure it's start off a start node, and end off a end node
+///
+/// every status lives in the arena of the NFA, and refers to the others by their index
+#[derive(Clone)]
 pub struct NFA {
-    start: StatusBox,
-    end: StatusBox,
+    states: Vec<State>,
+    start: StateId,
+    end: StateId,
+    /// the count of capturing groups, the whole match is not counted
+    groups: usize,
+    /// the index of each named group
+    group_names: HashMap<String, usize>,
 }
 
-pub struct Status {
-    id: usize,
+#[derive(Clone)]
+pub struct State {
     status_type: StatusType,
-    pub status_set: Vec<(Box<dyn StatusTargetRule>, StatusBox)>,
+    /// the capture slot recording the position where a thread enters the status,
+    /// the group `i` starts at the slot `2i` and ends at the slot `2i + 1`
+    slot: Option<usize>,
+    /// the transitions in order, the earlier one is preferred by the groups
+    pub transitions: Vec<(Transition, StateId)>,
+}
+
+/// the way from a status to the next one
+pub enum Transition {
+    /// consumes no character
+    Epsilon,
+    /// consumes a character accepted by the rule
+    Rule(Box<dyn StatusTargetRule>),
+    /// consumes no character, passed only if the assertion holds at the position
+    Assertion(Assertion),
+}
+
+impl Transition {
+    pub fn is_epsilon(&self) -> bool {
+        matches!(self, Transition::Epsilon)
+    }
+
+    /// whether the transition consumes the character `text`
+    pub fn accepts(&self, text: char) -> bool {
+        match self {
+            Transition::Rule(rule) => rule.input(text),
+            _ => false,
+        }
+    }
+
+    /// whether the transition is passed without consuming any character
+    /// between the characters before and after
+    pub(crate) fn is_passed(&self, before: Look, after: Look) -> bool {
+        match self {
+            Transition::Epsilon => true,
+            Transition::Rule(_) => false,
+            Transition::Assertion(assertion) => assertion.is_satisfied(before, after),
+        }
+    }
+
+    /// the rule of the transition consuming a character
+    pub fn rule(&self) -> Option<&dyn StatusTargetRule> {
+        match self {
+            Transition::Rule(rule) => Some(rule.as_ref()),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl Clone for Transition {
+    fn clone(&self) -> Self {
+        match self {
+            Transition::Epsilon => Transition::Epsilon,
+            Transition::Rule(rule) => Transition::Rule((**rule).clone()),
+            Transition::Assertion(assertion) => Transition::Assertion(*assertion),
+        }
+    }
+}
+
+/// what an assertion can see of a character next to a position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Look {
+    /// there is no character, the position is at the start or the end of the text
+    pub(crate) edge: bool,
+    pub(crate) new_line: bool,
+    /// a word character of `AlphanumericUnderlineRule`
+    pub(crate) word: bool,
+    /// a Unicode letter, digit or `_`
+    pub(crate) unicode_word: bool,
+}
+
+impl Look {
+    pub(crate) fn of(c: Option<char>) -> Self {
+        Self {
+            edge: c.is_none(),
+            new_line: c == Some('\n'),
+            word: c.is_some_and(|c| AlphanumericUnderlineRule.input(c)),
+            unicode_word: c.is_some_and(|c| c.is_alphanumeric() || c == '_'),
+        }
+    }
+
+    /// the characters looking different from the others,
+    /// the Unicode word characters are only told apart if `unicode` is set, they are many ranges
+    pub(crate) fn ranges(unicode: bool) -> Vec<(char, char)> {
+        let mut ranges = vec![('\n', '\n')];
+        ranges.extend_from_slice(AlphanumericUnderlineRule::RANGES);
+
+        if unicode {
+            let mut words: Vec<(char, char)> = Vec::new();
+            for c in ('\0'..=char::MAX).filter(|&c| Self::of(Some(c)).unicode_word) {
+                match words.last_mut() {
+                    Some((_, to)) if next_char(*to) == Some(c) => *to = c,
+                    _ => words.push((c, c)),
+                }
+            }
+            ranges.extend(words);
+        }
+
+        ranges
+    }
+}
+
+impl Assertion {
+    /// whether the assertion holds between the characters before and after
+    pub(crate) fn is_satisfied(&self, before: Look, after: Look) -> bool {
+        match self {
+            Assertion::StartText => before.edge,
+            Assertion::EndText => after.edge,
+            Assertion::StartLine => before.edge || before.new_line,
+            Assertion::EndLine => after.edge || after.new_line,
+            Assertion::WordBoundary { unicode: false } => before.word != after.word,
+            Assertion::WordBoundary { unicode: true } => before.unicode_word != after.unicode_word,
+            Assertion::NotWordBoundary { unicode: false } => before.word == after.word,
+            Assertion::NotWordBoundary { unicode: true } => {
+                before.unicode_word == after.unicode_word
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StatusType {
     Start,
     Node,
@@ -38,34 +165,35 @@ pub enum StatusType {
 
 macro_rules! init_start_rule {
     ($nfa: ident, $rule: expr) => {{
-        let mut status = $nfa.start.borrow_mut();
-        assert_eq!(status.status_set.len(), 1);
-        let record = status.status_set.get_mut(0).unwrap();
+        let status = &mut $nfa.states[$nfa.start];
+        assert_eq!(status.transitions.len(), 1);
+        let record = status.transitions.get_mut(0).unwrap();
 
-        record.0 = $rule;
+        record.0 = Transition::Rule($rule);
     }};
 }
 
 impl NFA {
     /// creates a default NFA,
-    /// representing ((start)) -EMPTY-> ((end))
+    /// representing ((start)) -empty-> ((end))
     pub fn new() -> Self {
-        let start_node = Status::start();
-        let end_node = Status::end();
-
-        RefCell::borrow_mut(&start_node).append_next(EMPTY, Rc::clone(&end_node));
+        let mut nfa = NFA {
+            states: vec![State::start(), State::end()],
+            start: 0,
+            end: 1,
+            groups: 0,
+            group_names: HashMap::new(),
+        };
+        nfa.append_empty(nfa.start, nfa.end);
 
-        NFA {
-            start: start_node,
-            end: end_node,
-        }
+        nfa
     }
 
     /// creates a NFA with text by default
     ///
     /// ((s)) -text-> ((e))
     pub fn with(text: char) -> Self {
-        let nfa = Self::new();
+        let mut nfa = Self::new();
         init_start_rule!(nfa, SingleCharRule::boxed(text));
 
         nfa
@@ -75,7 +203,7 @@ impl NFA {
     ///
     /// ((s)) -[a-zA-Z]-> ((e))
     pub fn with_alpha() -> Self {
-        let nfa = Self::new();
+        let mut nfa = Self::new();
         init_start_rule!(nfa, AlphaRule::boxed());
 
         nfa
@@ -85,7 +213,7 @@ impl NFA {
     ///
     /// ((s)) -[a-zA-Z0-9_]-> ((e))
     pub fn with_alphanumeric_underline() -> Self {
-        let nfa = Self::new();
+        let mut nfa = Self::new();
         init_start_rule!(nfa, AlphanumericUnderlineRule::boxed());
 
         nfa
@@ -95,7 +223,7 @@ impl NFA {
     ///
     /// ((s)) -[a-z]-> ((e))
     pub fn with_alpha_lowercase() -> Self {
-        let nfa = Self::new();
+        let mut nfa = Self::new();
         init_start_rule!(nfa, AlphaLowercaseRule::boxed());
 
         nfa
@@ -105,7 +233,7 @@ impl NFA {
     ///
     /// ((s)) -[A-Z]-> ((e))
     pub fn with_alpha_uppercase() -> Self {
-        let nfa = Self::new();
+        let mut nfa = Self::new();
         init_start_rule!(nfa, AlphaUppercaseRule::boxed());
 
         nfa
@@ -115,14 +243,166 @@ impl NFA {
     ///
     /// ((s)) -[0-9]-> ((e))
     pub fn with_digit() -> Self {
-        let nfa = Self::new();
+        let mut nfa = Self::new();
         init_start_rule!(nfa, Digit::boxed());
 
         nfa
     }
 
-    pub fn get_start(&self) -> StatusBox {
-        Rc::clone(&self.start)
+    /// creates a NFA with \W by default
+    ///
+    /// ((s)) -[^a-zA-Z0-9_]-> ((e))
+    pub fn with_not_alphanumeric_underline() -> Self {
+        Self::with_rule(NegatedRule::boxed(AlphanumericUnderlineRule::boxed()))
+    }
+
+    /// creates a NFA with \D by default
+    ///
+    /// ((s)) -[^0-9]-> ((e))
+    pub fn with_not_digit() -> Self {
+        Self::with_rule(NegatedRule::boxed(Digit::boxed()))
+    }
+
+    /// creates a NFA with \s by default
+    ///
+    /// ((s)) -[ \t\n\x0B\x0C\r]-> ((e))
+    pub fn with_whitespace() -> Self {
+        Self::with_rule(WhitespaceRule::boxed())
+    }
+
+    /// creates a NFA with \S by default
+    ///
+    /// ((s)) -[^ \t\n\x0B\x0C\r]-> ((e))
+    pub fn with_not_whitespace() -> Self {
+        Self::with_rule(NegatedRule::boxed(WhitespaceRule::boxed()))
+    }
+
+    /// creates a NFA with any char by default, `\n` is only included if `new_line` is set
+    ///
+    /// ((s)) -.-> ((e))
+    pub fn with_any_char(new_line: bool) -> Self {
+        Self::with_rule(AnyCharRule::boxed(new_line))
+    }
+
+    /// creates a NFA with a whole class of sorted ranges by default
+    ///
+    /// ((s)) -[a-f0-9_]-> ((e))
+    pub fn with_ranges(ranges: &[(char, char)]) -> Self {
+        Self::with_rule(IntervalSetRule::boxed(ranges.iter().copied()))
+    }
+
+    fn with_rule(rule: Box<dyn StatusTargetRule>) -> Self {
+        let mut nfa = Self::new();
+        init_start_rule!(nfa, rule);
+
+        nfa
+    }
+
+    pub fn get_start(&self) -> StateId {
+        self.start
+    }
+
+    pub fn get_end(&self) -> StateId {
+        self.end
+    }
+
+    /// the status of the id
+    ///
+    /// # Panics
+    /// panics if the status is not in this NFA
+    pub fn state(&self, id: StateId) -> &State {
+        &self.states[id]
+    }
+
+    /// the count of status, the ids are dense from 0 to the count,
+    /// numbered in the order the status are created, so the same pattern always gets the same ids
+    pub fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    /// the count of capturing groups, the whole match is not counted
+    pub fn groups(&self) -> usize {
+        self.groups
+    }
+
+    /// the index of the group named `name`
+    pub fn group_index(&self, name: &str) -> Option<usize> {
+        self.group_names.get(name).copied()
+    }
+
+    /// the name of every group in order, starting with the group 0 which is the whole match,
+    /// `None` for the groups without name
+    pub fn group_names(&self) -> impl Iterator<Item = Option<&str>> {
+        let mut names = vec![None; self.groups + 1];
+        for (name, &index) in self.group_names.iter() {
+            names[index] = Some(name.as_str());
+        }
+
+        names.into_iter()
+    }
+
+    /// takes the groups of the NFA merged into this one
+    fn merge_groups(&mut self, nfa: &NFA) {
+        self.groups = self.groups.max(nfa.groups);
+        self.group_names.extend(
+            nfa.group_names
+                .iter()
+                .map(|(name, &index)| (name.clone(), index)),
+        );
+    }
+
+    /// moves every status of the NFA into this one, and takes its groups
+    ///
+    /// # Return
+    /// the start and the end of the NFA within this one
+    fn absorb(&mut self, nfa: NFA) -> (StateId, StateId) {
+        let offset = self.states.len();
+        self.merge_groups(&nfa);
+
+        self.states.extend(nfa.states.into_iter().map(|mut status| {
+            for (_, next) in status.transitions.iter_mut() {
+                *next += offset;
+            }
+            status
+        }));
+
+        (nfa.start + offset, nfa.end + offset)
+    }
+
+    /// appends the transition from the status `from` to the status `to`,
+    /// `to` is not a start any more once it's reached from another status
+    fn append(&mut self, from: StateId, transition: Transition, to: StateId) {
+        let target = &mut self.states[to];
+        if target.status_type == StatusType::Start {
+            target.turn_to_empty();
+        }
+        self.states[from].transitions.push((transition, to));
+    }
+
+    /// appends the transition consuming no character
+    fn append_empty(&mut self, from: StateId, to: StateId) {
+        self.append(from, Transition::Epsilon, to);
+    }
+
+    /// creates a NFA passing only where the assertion holds
+    ///
+    /// ((s)) -assertion-> ((e))
+    pub fn with_assertion(assertion: Assertion) -> Self {
+        let mut nfa = Self::new();
+        nfa.states[nfa.start].transitions.clear();
+        nfa.append(nfa.start, Transition::Assertion(assertion), nfa.end);
+
+        nfa
+    }
+
+    /// creates a NFA recording the position into the capture slot when it's passed
+    ///
+    /// ((s, slot)) -empty-> ((e))
+    fn with_slot(slot: usize) -> Self {
+        let mut nfa = Self::new();
+        nfa.states[nfa.start].slot = Some(slot);
+
+        nfa
     }
 
     /// connects two NFAs
@@ -135,19 +415,14 @@ impl NFA {
     /// after:
     /// ((s)) -a-> ((empty)) -empty-> ((empty)) -> -b-> ((e))
     pub fn and(&mut self, nfa: NFA) -> &mut Self {
-        {
-            let mut end1 = self.end.borrow_mut();
-            end1.turn_to_empty();
+        let (start2, end2) = self.absorb(nfa);
 
-            {
-                let mut start2 = nfa.start.borrow_mut();
-                start2.turn_to_empty();
-            }
+        self.states[self.end].turn_to_empty();
+        self.states[start2].turn_to_empty();
+        self.append_empty(self.end, start2);
 
-            end1.append_next(EMPTY, nfa.start);
-        }
-        self.end = nfa.end;
-        return self;
+        self.end = end2;
+        self
     }
 
     /// or two NFAs
@@ -162,343 +437,654 @@ impl NFA {
     /// ((s)) --                                      -> ((e))
     ///        \-empty->(empty) -b-> (empty) -empty-/
     pub fn or(self, nfa: NFA) -> Self {
-        let new_nfa = NFA::new();
-        let new_end = Rc::clone(&new_nfa.end);
+        let mut new_nfa = NFA::new();
+        let (new_start, new_end) = (new_nfa.start, new_nfa.end);
+        new_nfa.states[new_start].transitions.clear();
 
-        let mut start = RefCell::borrow_mut(&new_nfa.start);
-        start.status_set.clear();
-        {
-            let mut or_start_1 = RefCell::borrow_mut(&self.start);
-            let mut or_end_1 = RefCell::borrow_mut(&self.end);
+        for branch in [self, nfa] {
+            let (or_start, or_end) = new_nfa.absorb(branch);
 
-            or_start_1.turn_to_empty();
-            or_end_1.turn_to_empty();
+            new_nfa.states[or_start].turn_to_empty();
+            new_nfa.states[or_end].turn_to_empty();
+            new_nfa.append_empty(or_end, new_end);
 
-            or_end_1.append_next(EMPTY, Rc::clone(&new_end));
+            new_nfa.append_empty(new_start, or_start);
+        }
 
-            let mut or_start_2 = RefCell::borrow_mut(&nfa.start);
-            let mut or_end_2 = RefCell::borrow_mut(&nfa.end);
+        new_nfa
+    }
 
-            or_start_2.turn_to_empty();
-            or_end_2.turn_to_empty();
-            or_end_2.append_next(EMPTY, Rc::clone(&new_end));
+    /// joins the rules with a shared start like `or`, but every rule keeps its own end
+    ///
+    /// # Return
+    /// the NFA, and the end of each rule within it, the end of the NFA itself is never reached
+    pub(crate) fn union(rules: Vec<NFA>) -> (Self, Vec<StateId>) {
+        let mut union = NFA::new();
+        let start = union.start;
+        union.states[start].transitions.clear();
+
+        let mut ends = Vec::with_capacity(rules.len());
+        for rule in rules {
+            let (rule_start, rule_end) = union.absorb(rule);
+            union.append_empty(start, rule_start);
+            ends.push(rule_end);
         }
 
-        start.append_next(EMPTY, Rc::clone(&self.start));
-        start.append_next(EMPTY, Rc::clone(&nfa.start));
+        (union, ends)
+    }
 
-        drop(start);
+    /// repeats the NFA zero or more times, `a*`
+    ///
+    /// before:
+    /// A: ((s)) -a-> ((e))
+    ///
+    /// after:
+    ///                    /-------empty--------\
+    ///                    v                     |
+    /// ((s)) -empty-> (empty) -a-> (empty) -empty-> ((e))
+    ///   \----------------------empty---------------/^
+    pub fn star(self) -> Self {
+        self.repeat_with(true, true)
+    }
 
-        new_nfa
+    /// repeats the NFA one or more times, `a+`
+    ///
+    /// before:
+    /// A: ((s)) -a-> ((e))
+    ///
+    /// after:
+    ///                    /-------empty--------\
+    ///                    v                     |
+    /// ((s)) -empty-> (empty) -a-> (empty) -empty-> ((e))
+    pub fn plus(self) -> Self {
+        self.repeat_with(true, false)
     }
-}
 
-#[derive(Debug)]
-pub enum NFAError {
-    ParseWrong,
-}
+    /// makes the NFA optional, `a?`
+    ///
+    /// before:
+    /// A: ((s)) -a-> ((e))
+    ///
+    /// after:
+    /// ((s)) -empty-> (empty) -a-> (empty) -empty-> ((e))
+    ///   \----------------------empty---------------/^
+    pub fn optional(self) -> Self {
+        self.repeat_with(false, true)
+    }
 
-impl FromStr for NFA {
-    type Err = NFAError;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut start = NFA::new();
-        let mut chars = s.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            match c {
-                '\\' => {
-                    if let Some(nfa) = Self::handle_backslash(&mut chars) {
-                        start.and(nfa);
-                    }
-                }
-                '[' => {
-                    let nfa = Self::handle_bracket(&mut chars);
-                    if nfa.is_some() {
-                        start.and(nfa.unwrap());
-                    }
-                }
-                c => {
-                    let next = NFA::with(c);
-                    start.and(next);
-                }
-            }
+    /// wraps the NFA with a new start and a new end,
+    /// `looped` links the inner end back to the inner start,
+    /// `skippable` links the new start to the new end directly
+    fn repeat_with(self, looped: bool, skippable: bool) -> Self {
+        let mut new_nfa = NFA::new();
+        let (start, end) = (new_nfa.start, new_nfa.end);
+        new_nfa.states[start].transitions.clear();
+
+        let (inner_start, inner_end) = new_nfa.absorb(self);
+        new_nfa.states[inner_end].turn_to_empty();
+        if looped {
+            new_nfa.append_empty(inner_end, inner_start);
         }
+        new_nfa.append_empty(inner_end, end);
 
-        Ok(start)
+        new_nfa.append_empty(start, inner_start);
+        if skippable {
+            new_nfa.append_empty(start, end);
+        }
+
+        new_nfa
     }
-}
 
-impl NFA {
-    /// handle backslash symbol
-    /// \w
+    /// repeats the NFA at least `min` times and at most `max` times,
+    /// `max` of `None` means unbounded, `a{m}`, `a{m,}`, `a{m,n}`
     ///
-    /// # Return
-    /// index of end of symbol
-    fn handle_backslash(chars: &mut impl Iterator<Item = char>) -> Option<NFA> {
-        let mut res_nfa = None;
+    /// before:
+    /// A: ((s)) -a-> ((e))
+    ///
+    /// after, `a{1,2}`:
+    /// ((s)) -empty-> (empty) -a-> (empty) -empty-> (empty?) -a-> (empty) -empty-> ((e))
+    ///
+    /// # Panics
+    /// panics if `min` is greater than `max`, or the count is over `MAX_REPEAT`
+    pub fn repeat(self, min: usize, max: Option<usize>) -> Self {
+        if let Some(max) = max {
+            assert!(min <= max, "repeat min {} is greater than max {}", min, max);
+        }
+        let count = max.unwrap_or(min);
+        assert!(
+            count <= MAX_REPEAT,
+            "repeat count {} is over {}",
+            count,
+            MAX_REPEAT
+        );
 
-        match chars.next() {
-            Some('w') => {
-                res_nfa = Some(NFA::with_alphanumeric_underline());
+        let mut nfa = NFA::new();
+        for _ in 0..min {
+            nfa.and(self.clone());
+        }
+
+        match max {
+            None => {
+                nfa.and(self.star());
             }
-            Some('\\') => {
-                res_nfa = Some(NFA::with('\\'));
+            Some(max) => {
+                for _ in min..max {
+                    nfa.and(self.clone().optional());
+                }
             }
-            Some(c) => panic!("unsopperted symbol: {}", c),
-            _ => {}
         }
 
-        res_nfa
+        nfa
     }
+}
 
-    fn handle_bracket(chars: &mut Peekable<Chars>) -> Option<NFA> {
-        let mut is_lowercase_alphabet = false;
-        let mut is_uppercase_alphabet = false;
-        let mut is_digit = false;
+/// builds NFA from patterns with the flags set in advance
+#[derive(Debug, Clone, Default)]
+pub struct NFABuilder {
+    flags: Flags,
+}
 
-        let mut nfa: Option<NFA> = None;
+impl NFABuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        macro_rules! nfa_or {
-            ($new_nfa: expr) => {
-                if nfa.is_some() {
-                    nfa = Some(nfa.unwrap().or($new_nfa));
-                } else {
-                    nfa = Some($new_nfa);
-                }
-            };
-        }
+    /// makes `.` match `\n` as well, like the inline flag `(?s)`
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut Self {
+        self.flags.dot_matches_new_line = yes;
+        self
+    }
 
-        if chars.peek().is_none() {
-            return None;
-        }
+    /// makes `^` and `$` match at each line, like the inline flag `(?m)`
+    pub fn multi_line(&mut self, yes: bool) -> &mut Self {
+        self.flags.multi_line = yes;
+        self
+    }
 
-        while let Some(c) = chars.next() {
-            match c {
-                ']' => break,
-                'a' => {
-                    if let Some('-') = chars.peek() {
-                        chars.next().unwrap();
-                        if let Some('z') = chars.peek() {
-                            chars.next();
-                            is_lowercase_alphabet = true;
-                        } else {
-                            nfa_or!(NFA::with('a'));
-                            nfa_or!(NFA::with('-'));
-                        }
-                    }
-                }
-                'A' => {
-                    if let Some('-') = chars.peek() {
-                        chars.next().unwrap();
-                        if let Some('Z') = chars.peek() {
-                            chars.next();
-                            is_uppercase_alphabet = true;
-                        } else {
-                            nfa_or!(NFA::with('A'));
-                            nfa_or!(NFA::with('-'));
-                        }
-                    }
-                }
-                '0' => {
-                    if let Some('-') = chars.peek() {
-                        chars.next().unwrap();
-                        if let Some('9') = chars.peek() {
-                            chars.next();
-                            is_digit = true;
-                        } else {
-                            nfa_or!(NFA::with('0'));
-                            nfa_or!(NFA::with('-'));
-                        }
-                    }
-                }
-                '\\' => match chars.next() {
-                    Some('w') => nfa_or!(NFA::with_alphanumeric_underline()),
-                    Some('d') => nfa_or!(NFA::with_digit()),
-                    Some('\\') => nfa_or!(NFA::with('\\')),
-                    Some(s) => {
-                        panic!("typo symbol: \\{}", s)
-                    }
-                    None => panic!("typo: no more character"),
-                },
-                v => {
-                    nfa_or!(NFA::with(v));
-                }
-            }
-        }
+    /// makes `\b` and `\B` take the Unicode letters and digits as word characters,
+    /// like the inline flag `(?u)`
+    pub fn unicode_word(&mut self, yes: bool) -> &mut Self {
+        self.flags.unicode_word = yes;
+        self
+    }
 
-        if is_lowercase_alphabet {
-            nfa_or!(NFA::with_alpha_lowercase());
-        }
-        if is_uppercase_alphabet {
-            nfa_or!(NFA::with_alpha_uppercase())
-        }
-        if is_digit {
-            nfa_or!(NFA::with_digit())
-        }
+    pub fn build(&self, pattern: &str) -> Result<NFA, NFAError> {
+        let ast = Ast::parse_with(pattern, self.flags)?;
 
-        nfa
+        Ok(NFA::from_ast(&ast))
     }
 }
 
-impl Status {
-    pub fn start() -> StatusBox {
-        Rc::new(RefCell::new(Self {
-            id: new_id(),
-            status_type: StatusType::Start,
-            status_set: Vec::new(),
-        }))
+impl Default for NFA {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn end() -> StatusBox {
-        Rc::new(RefCell::new(Self {
-            id: new_id(),
-            status_type: StatusType::End,
-            status_set: Vec::new(),
-        }))
+/// the greatest count of a counted repetition, like `a{1000}`
+pub const MAX_REPEAT: usize = 1000;
+
+/// the greatest count of atoms a pattern may grow to once its counted repetitions are expanded,
+/// like `(a{100}){100}` which has 10000
+pub const MAX_EXPANDED_LEN: usize = 100_000;
+
+/// the error of parsing a pattern,
+/// carrying where it happens as byte offset and byte length within the pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NFAError {
+    kind: NFAErrorKind,
+    offset: usize,
+    len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NFAErrorKind {
+    /// `\` followed by a character which is not a known escape, like `\q`
+    UnknownEscape(char),
+    /// `\` at the end of the pattern
+    TrailingBackslash,
+    /// `[` without its `]`
+    UnclosedBracket,
+    /// `(` without its `)`
+    UnclosedParenthesis,
+    /// `)` without its `(`
+    UnbalancedParenthesis,
+    /// quantifier with nothing to repeat, like `*a`
+    DanglingQuantifier,
+    /// class without any member, `[]`
+    EmptyClass,
+    /// range whose start is greater than its end, like `{3,2}`
+    BadRange,
+    /// escape sequence which is malformed, like `\xZZ` or `\u{110000}`
+    InvalidEscape,
+    /// flag which is not known, like `(?x)`
+    UnknownFlag(char),
+    /// group name which is empty or has a character other than letters, digits and underlines,
+    /// or starts with a digit, like `(?<1st>a)`
+    InvalidGroupName,
+    /// group name which is used by an earlier group
+    DuplicateGroupName(String),
+    /// counted repetition over `MAX_REPEAT`, like `a{1001}`,
+    /// or growing the pattern over `MAX_EXPANDED_LEN`, like `(a{1000}){1000}`
+    RepeatTooLarge,
+}
+
+impl NFAError {
+    pub fn new(kind: NFAErrorKind, offset: usize, len: usize) -> Self {
+        Self { kind, offset, len }
     }
 
-    pub fn node() -> StatusBox {
-        Rc::new(RefCell::new(Self {
-            id: new_id(),
-            status_type: StatusType::Node,
-            status_set: Vec::new(),
-        }))
+    pub fn kind(&self) -> &NFAErrorKind {
+        &self.kind
     }
 
-    pub fn id(&self) -> usize {
-        self.id
+    /// byte offset of the wrong part within the pattern
+    pub fn offset(&self) -> usize {
+        self.offset
     }
 
-    pub fn append_next(&mut self, text: char, status: StatusBox) {
-        {
-            let mut temp = RefCell::borrow_mut(&status);
-            if temp.status_type == StatusType::Start {
-                temp.turn_to_empty();
-            }
-        }
-        self.status_set.push((SingleCharRule::boxed(text), status));
+    /// byte range of the wrong part within the pattern
+    pub fn span(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
     }
 
-    pub fn turn_to_empty(&mut self) {
-        self.status_type = StatusType::Node
+    /// renders the error with the pattern, marking the wrong part with carets
+    ///
+    /// ```text
+    /// unknown escape sequence `\q`
+    /// a\qb
+    ///  ^^
+    /// ```
+    pub fn render(&self, pattern: &str) -> String {
+        let offset = self.offset.min(pattern.len());
+        let end = (self.offset + self.len).min(pattern.len());
+
+        let column = pattern[..offset].chars().count();
+        let width = pattern[offset..end].chars().count().max(1);
+
+        format!(
+            "{}\n{}\n{}{}",
+            self,
+            pattern,
+            " ".repeat(column),
+            "^".repeat(width)
+        )
     }
+}
 
-    pub fn next(&self, text: char) -> Option<StatusBox> {
-        self.status_set.iter().find_map(|v| {
-            if v.0.input(text) {
-                Some(Rc::clone(&v.1))
-            } else {
-                None
+impl Display for NFAError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            NFAErrorKind::UnknownEscape(c) => write!(f, "unknown escape sequence `\\{}`", c),
+            NFAErrorKind::TrailingBackslash => write!(f, "pattern ends with a backslash"),
+            NFAErrorKind::UnclosedBracket => write!(f, "unclosed bracket `[`"),
+            NFAErrorKind::UnclosedParenthesis => write!(f, "unclosed parenthesis `(`"),
+            NFAErrorKind::UnbalancedParenthesis => write!(f, "unbalanced parenthesis `)`"),
+            NFAErrorKind::DanglingQuantifier => write!(f, "quantifier has nothing to repeat"),
+            NFAErrorKind::EmptyClass => write!(f, "empty class `[]`"),
+            NFAErrorKind::BadRange => write!(f, "range start is greater than range end"),
+            NFAErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            NFAErrorKind::UnknownFlag(c) => write!(f, "unknown flag `{}`", c),
+            NFAErrorKind::InvalidGroupName => write!(f, "invalid group name"),
+            NFAErrorKind::DuplicateGroupName(name) => {
+                write!(f, "duplicate group name `{}`", name)
             }
-        })
+            NFAErrorKind::RepeatTooLarge => write!(f, "counted repetition is too large"),
+        }?;
+
+        write!(f, " at {}", self.offset)
     }
+}
 
-    pub fn next_skip_empty(&self, text: char) -> Option<StatusBox> {
-        for status in self.status_set.iter() {
-            let cur = match &status {
-                &(rule, ref next) if rule.input(EMPTY) => {
-                    let next = RefCell::borrow(&next);
-                    let next = next.next_skip_empty(text);
-                    if next.is_none() {
-                        continue;
-                    }
-                    next
-                }
-                &(target, ref next) if target.input(text) => Some(Rc::clone(next)),
-                _ => None,
-            };
+impl Error for NFAError {}
 
-            if cur.is_some() {
-                return cur;
+impl FromStr for NFA {
+    type Err = NFAError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ast = Ast::from_str(s)?;
+
+        Ok(Self::from_ast(&ast))
+    }
+}
+
+impl NFA {
+    /// compiles the ast into NFA,
+    /// the groups are numbered from 1 in the order of their opening parentheses
+    ///
+    /// # Panics
+    /// panics if the ast expands over `MAX_EXPANDED_LEN` atoms,
+    /// which the parser reports as `NFAErrorKind::RepeatTooLarge`
+    pub fn from_ast(ast: &Ast) -> Self {
+        let len = ast.expanded_len();
+        assert!(
+            len <= MAX_EXPANDED_LEN,
+            "ast expands to {} atoms, over {}",
+            len,
+            MAX_EXPANDED_LEN
+        );
+
+        Self::compile(ast, &mut 0)
+    }
+
+    /// `groups` is the count of groups compiled so far
+    fn compile(ast: &Ast, groups: &mut usize) -> Self {
+        match ast {
+            Ast::Empty => NFA::new(),
+            Ast::Literal(c) => NFA::with(*c),
+            Ast::Any { new_line } => NFA::with_any_char(*new_line),
+            Ast::Assertion(assertion) => NFA::with_assertion(*assertion),
+            Ast::Class(Class::Perl(perl)) => Self::from_perl_class(*perl),
+            Ast::Class(Class::Bracket { negated, items }) => {
+                let ranges = items.iter().flat_map(|item| match item {
+                    ClassItem::Char(c) => vec![(*c, *c)],
+                    ClassItem::Range(from, to) => vec![(*from, *to)],
+                    ClassItem::Perl(perl) => Self::perl_class_ranges(*perl),
+                });
+
+                let rule = if *negated {
+                    IntervalSetRule::new(ranges).negate()
+                } else {
+                    IntervalSetRule::new(ranges)
+                };
+                Self::with_rule(Box::new(rule))
+            }
+            Ast::Concat(asts) => {
+                let mut nfa = NFA::new();
+                for ast in asts {
+                    nfa.and(Self::compile(ast, groups));
+                }
+                nfa
             }
+            Ast::Alternation(asts) => asts
+                .iter()
+                .map(|ast| Self::compile(ast, groups))
+                .reduce(NFA::or)
+                .unwrap_or_default(),
+            Ast::Repeat { ast, min, max } => {
+                let nfa = Self::compile(ast, groups);
+                match (min, max) {
+                    (0, None) => nfa.star(),
+                    (1, None) => nfa.plus(),
+                    (0, Some(1)) => nfa.optional(),
+                    (min, max) => nfa.repeat(*min, *max),
+                }
+            }
+            Ast::Group(ast) => Self::compile_group(ast, None, groups),
+            Ast::NamedGroup { name, ast } => Self::compile_group(ast, Some(name), groups),
         }
+    }
 
-        None
+    /// ((s, 2i)) -empty-> ... inner ... -empty-> ((2i + 1)) -empty-> ((e))
+    fn compile_group(ast: &Ast, name: Option<&str>, groups: &mut usize) -> Self {
+        *groups += 1;
+        let index = *groups;
+
+        let mut nfa = NFA::with_slot(2 * index);
+        nfa.and(Self::compile(ast, groups));
+        nfa.and(NFA::with_slot(2 * index + 1));
+
+        nfa.groups = nfa.groups.max(index);
+        if let Some(name) = name {
+            nfa.group_names.insert(name.to_string(), index);
+        }
+        nfa
     }
 
-    pub fn next_all_skip_empty(&self) -> Vec<StatusBox> {
-        let mut res = Vec::new();
+    fn from_perl_class(perl: PerlClass) -> Self {
+        match perl {
+            PerlClass::Word => NFA::with_alphanumeric_underline(),
+            PerlClass::NotWord => NFA::with_not_alphanumeric_underline(),
+            PerlClass::Digit => NFA::with_digit(),
+            PerlClass::NotDigit => NFA::with_not_digit(),
+            PerlClass::Space => NFA::with_whitespace(),
+            PerlClass::NotSpace => NFA::with_not_whitespace(),
+        }
+    }
 
-        res
+    fn perl_class_ranges(perl: PerlClass) -> Vec<(char, char)> {
+        let (ranges, negated) = match perl {
+            PerlClass::Word => (AlphanumericUnderlineRule::RANGES, false),
+            PerlClass::NotWord => (AlphanumericUnderlineRule::RANGES, true),
+            PerlClass::Digit => (Digit::RANGES, false),
+            PerlClass::NotDigit => (Digit::RANGES, true),
+            PerlClass::Space => (WhitespaceRule::RANGES, false),
+            PerlClass::NotSpace => (WhitespaceRule::RANGES, true),
+        };
+
+        if negated {
+            IntervalSetRule::new(ranges.iter().copied())
+                .negate()
+                .ranges()
+                .to_vec()
+        } else {
+            ranges.to_vec()
+        }
     }
+}
 
-    /// Status set that can reaches from status of NFA through empty
-    pub fn closure_s(status: &StatusBox) -> Vec<StatusBox> {
-        let mut res = Vec::new();
+impl NFA {
+    /// the first status reached from the status through empty transitions,
+    /// which then accepts `text`
+    pub fn next_skip_empty(&self, status: StateId, text: char) -> Option<StateId> {
+        self._next_skip_empty(status, text, &mut HashSet::new())
+    }
 
-        res.push(Rc::clone(status));
+    /// `visited` keeps the ids already walked through, empty loops of `*` and `+` would recurse forever otherwise
+    fn _next_skip_empty(
+        &self,
+        status: StateId,
+        text: char,
+        visited: &mut HashSet<StateId>,
+    ) -> Option<StateId> {
+        if !visited.insert(status) {
+            return None;
+        }
 
-        let s_res = Self::_closure_s(status);
-        res.extend(s_res);
+        for (transition, next) in self.states[status].transitions.iter() {
+            if transition.is_epsilon() {
+                if let Some(found) = self._next_skip_empty(*next, text, visited) {
+                    return Some(found);
+                }
+            } else if transition.accepts(text) {
+                return Some(*next);
+            }
+        }
 
-        res
+        None
+    }
+
+    /// whether the end status can be reached from the status through empty
+    pub fn is_end_skip_empty(&self, status: StateId) -> bool {
+        self._is_end_skip_empty(status, &mut HashSet::new())
     }
 
-    fn _closure_s(status: &StatusBox) -> Vec<StatusBox> {
-        let mut res = vec![Rc::clone(&status)];
-        let status_ref = RefCell::borrow(&status);
-        let mut cur_used = false;
+    fn _is_end_skip_empty(&self, status: StateId, visited: &mut HashSet<StateId>) -> bool {
+        if self.states[status].status_type == StatusType::End {
+            return true;
+        }
+        if !visited.insert(status) {
+            return false;
+        }
+
+        self.states[status]
+            .transitions
+            .iter()
+            .any(|(transition, next)| {
+                transition.is_epsilon() && self._is_end_skip_empty(*next, visited)
+            })
+    }
 
-        for item in status_ref.status_set.iter() {
-            if item.0.input(EMPTY) {
-                let r = Self::closure_s(&item.1);
-                res.extend(r);
-            } else if !cur_used {
-                res.push(Rc::clone(&status));
-                cur_used = true;
+    /// Status set that can reaches from status of NFA through empty
+    pub fn closure_s(&self, status: StateId) -> Vec<StateId> {
+        let mut res = Vec::new();
+        let mut visited = vec![false; self.states.len()];
+        let mut stack = vec![status];
+
+        while let Some(status) = stack.pop() {
+            if std::mem::replace(&mut visited[status], true) {
+                continue;
+            }
+
+            for (transition, next) in self.states[status].transitions.iter() {
+                if transition.is_epsilon() {
+                    stack.push(*next);
+                }
             }
+
+            res.push(status);
         }
 
         res
     }
 
     /// the set that some closure_s of T
-    fn closure_t(status_t: Vec<StatusBox>) -> Vec<StatusBox> {
+    pub fn closure_t(&self, status_t: Vec<StateId>) -> Vec<StateId> {
         let mut res = Vec::new();
 
         for status in status_t {
-            let t_res = Self::closure_s(&status);
+            let t_res = self.closure_s(status);
             res.extend(t_res);
         }
 
         res
     }
 
-    pub fn closure_t_a(
-        status_t: &Vec<StatusBox>,
-        status_target_rule: Box<dyn StatusTargetRule>,
-    ) -> Vec<StatusBox> {
+    /// the set reachable from T through the transitions accepting `text`,
+    /// the empty transitions are not followed
+    pub fn closure_t_a(&self, status_t: &[StateId], text: char) -> Vec<StateId> {
         let mut res = Vec::new();
 
-        for status in status_t {
-            let status = RefCell::borrow(&status);
-            let throughable = status.status_set.iter().filter_map(|item| {
-                let is_eq = (*item.0).eq(&*status_target_rule);
-                if is_eq {
-                    Some(Rc::clone(&item.1))
-                } else {
-                    None
-                }
-            });
+        for &status in status_t {
+            let throughable = self.states[status]
+                .transitions
+                .iter()
+                .filter(|(transition, _)| transition.accepts(text))
+                .map(|(_, next)| *next);
 
             res.extend(throughable);
         }
 
         res
     }
+}
+
+impl NFA {
+    /// renders the NFA in Graphviz DOT, the start is bold and the end is a double circle,
+    /// the empty transitions are dashed, the assertions are dotted,
+    /// and the others are labelled with the characters they accept
+    ///
+    /// ```text
+    /// digraph NFA {
+    ///     rankdir=LR;
+    ///     node [shape=circle];
+    ///     entry [shape=point];
+    ///     entry -> 0;
+    ///     0 [style=bold];
+    ///     1 [shape=doublecircle];
+    ///     0 -> 1 [label="a"];
+    /// }
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![
+            "digraph NFA {".to_string(),
+            "    rankdir=LR;".to_string(),
+            "    node [shape=circle];".to_string(),
+            "    entry [shape=point];".to_string(),
+            format!("    entry -> {};", self.start),
+        ];
+
+        for (id, status) in self.states.iter().enumerate() {
+            let mut attributes = Vec::new();
+            match status.status_type {
+                StatusType::Start => attributes.push("style=bold".to_string()),
+                StatusType::End => attributes.push("shape=doublecircle".to_string()),
+                StatusType::Node => {}
+            }
+            if let Some(slot) = status.slot {
+                attributes.push(format!("label=\"{}\\nslot {}\"", id, slot));
+            }
+
+            match attributes.is_empty() {
+                true => lines.push(format!("    {};", id)),
+                false => lines.push(format!("    {} [{}];", id, attributes.join(", "))),
+            }
+        }
+
+        for (id, status) in self.states.iter().enumerate() {
+            for (transition, next) in status.transitions.iter() {
+                let attributes = match transition {
+                    Transition::Epsilon => "label=\"ε\", style=dashed".to_string(),
+                    Transition::Rule(rule) => format!("label={}", quote(&rule.to_string())),
+                    Transition::Assertion(assertion) => {
+                        format!("label={}, style=dotted", quote(&assertion.to_string()))
+                    }
+                };
+                lines.push(format!("    {} -> {} [{}];", id, next, attributes));
+            }
+        }
+
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+}
+
+impl State {
+    pub fn start() -> Self {
+        Self {
+            status_type: StatusType::Start,
+            slot: None,
+            transitions: Vec::new(),
+        }
+    }
+
+    pub fn end() -> Self {
+        Self {
+            status_type: StatusType::End,
+            slot: None,
+            transitions: Vec::new(),
+        }
+    }
+
+    pub fn node() -> Self {
+        Self {
+            status_type: StatusType::Node,
+            slot: None,
+            transitions: Vec::new(),
+        }
+    }
+
+    pub fn slot(&self) -> Option<usize> {
+        self.slot
+    }
+
+    pub fn turn_to_empty(&mut self) {
+        self.status_type = StatusType::Node
+    }
+
+    pub fn next(&self, text: char) -> Option<StateId> {
+        self.transitions
+            .iter()
+            .find_map(|v| if v.0.accepts(text) { Some(v.1) } else { None })
+    }
+
+    /// the first status following an empty transition
+    pub fn next_empty(&self) -> Option<StateId> {
+        self.transitions
+            .iter()
+            .find(|(transition, _)| transition.is_epsilon())
+            .map(|(_, next)| *next)
+    }
+
+    pub fn next_all_skip_empty(&self) -> Vec<StateId> {
+        Vec::new()
+    }
 
     pub fn get_type(&self) -> &StatusType {
         &self.status_type
     }
 
     pub fn target_count(&self) -> usize {
-        self.status_set.len()
-    }
-}
-
-impl PartialEq for Status {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+        self.transitions.len()
     }
 }
 
@@ -509,6 +1095,11 @@ mod tests {
     use StatusType::*;
 
     macro_rules! check_status {
+        ($status: ident, $type: expr, $count: expr, empty, $has_next: expr) => {
+            assert_eq!($status.get_type(), &$type);
+            assert_eq!($status.target_count(), $count);
+            assert_eq!($status.next_empty().is_some(), $has_next);
+        };
         ($status: ident, $type: expr, $count: expr, $next_target: expr, $has_next: expr) => {
             assert_eq!($status.get_type(), &$type);
             assert_eq!($status.target_count(), $count);
@@ -523,24 +1114,23 @@ mod tests {
 
         let nfa = NFA::new();
 
-        let start = RefCell::borrow(&nfa.start);
+        let start = nfa.state(nfa.start);
         // let start: &_ = nfa.start.borrow();
         check_status!(
             start,
             StatusType::Start,
             START_STATUS_SHOULD_BE_ONLY_ONE_TARGET,
-            EMPTY,
+            empty,
             true
         );
 
-        let next = start.next(EMPTY).unwrap();
-        let next = RefCell::borrow(&next);
+        let next = nfa.state(start.next_empty().unwrap());
 
         check_status!(
             next,
             StatusType::End,
             END_STATUS_SHOULD_BE_ZERO_TARGET,
-            EMPTY,
+            empty,
             false
         );
     }
@@ -552,15 +1142,14 @@ mod tests {
         // ((s)) -text-> ((e))
         let nfa = NFA::with(INIT_CHAR);
 
-        let status = RefCell::borrow(&nfa.start);
+        let status = nfa.state(nfa.start);
 
-        check_status!(status, StatusType::Start, 1, EMPTY, false);
+        check_status!(status, StatusType::Start, 1, empty, false);
         assert!(status.next(INIT_CHAR).is_some());
 
-        let status = status.next(INIT_CHAR).unwrap();
-        let status = RefCell::borrow(&status);
+        let status = nfa.state(status.next(INIT_CHAR).unwrap());
 
-        check_status!(status, StatusType::End, 0, EMPTY, false);
+        check_status!(status, StatusType::End, 0, empty, false);
     }
 
     #[test]
@@ -573,24 +1162,21 @@ mod tests {
             ((s)) -empty-> ((empty)) -empty-> ((empty)) -> -empty-> ((e))
         */
 
-        let status = RefCell::borrow(&nfa_1.start);
+        let status = nfa_1.state(nfa_1.start);
 
-        check_status!(status, Start, 1, EMPTY, true);
+        check_status!(status, Start, 1, empty, true);
 
-        let status = status.next(EMPTY).unwrap();
-        let status = RefCell::borrow(&status);
+        let status = nfa_1.state(status.next_empty().unwrap());
 
-        check_status!(status, Node, 1, EMPTY, true);
+        check_status!(status, Node, 1, empty, true);
 
-        let status = status.next(EMPTY).unwrap();
-        let status = RefCell::borrow(&status);
+        let status = nfa_1.state(status.next_empty().unwrap());
 
-        check_status!(status, Node, 1, EMPTY, true);
+        check_status!(status, Node, 1, empty, true);
 
-        let status = status.next(EMPTY).unwrap();
-        let status = RefCell::borrow(&status);
+        let status = nfa_1.state(status.next_empty().unwrap());
 
-        check_status!(status, End, 0, EMPTY, false);
+        check_status!(status, End, 0, empty, false);
     }
 
     #[test]
@@ -603,34 +1189,30 @@ mod tests {
         // ((s)) --                                      -> ((e))
         //        \-empty->(empty) -empty-> (empty) -empty-/
 
-        let status = RefCell::borrow(&nfa_1.start);
-        check_status!(status, Start, 2, EMPTY, true);
+        let status = nfa_1.state(nfa_1.start);
+        check_status!(status, Start, 2, empty, true);
 
-        let status_pair = status.status_set.get(0).unwrap();
-        assert!(status_pair.0.input(EMPTY));
-        let branch = RefCell::borrow(&status_pair.1);
-        check_status!(branch, Node, 1, EMPTY, true);
+        let status_pair = status.transitions.first().unwrap();
+        assert!(status_pair.0.is_epsilon());
+        let branch = nfa_1.state(status_pair.1);
+        check_status!(branch, Node, 1, empty, true);
 
-        let branch = branch.next(EMPTY).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, Node, 1, EMPTY, true);
+        let branch = nfa_1.state(branch.next_empty().unwrap());
+        check_status!(branch, Node, 1, empty, true);
 
-        let branch = branch.next(EMPTY).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, End, 0, EMPTY, false);
+        let branch = nfa_1.state(branch.next_empty().unwrap());
+        check_status!(branch, End, 0, empty, false);
 
-        let status_pair = status.status_set.get(1).unwrap();
-        assert!(status_pair.0.input(EMPTY));
-        let branch = RefCell::borrow(&status_pair.1);
-        check_status!(branch, Node, 1, EMPTY, true);
+        let status_pair = status.transitions.get(1).unwrap();
+        assert!(status_pair.0.is_epsilon());
+        let branch = nfa_1.state(status_pair.1);
+        check_status!(branch, Node, 1, empty, true);
 
-        let branch = branch.next(EMPTY).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, Node, 1, EMPTY, true);
+        let branch = nfa_1.state(branch.next_empty().unwrap());
+        check_status!(branch, Node, 1, empty, true);
 
-        let branch = branch.next(EMPTY).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, End, 0, EMPTY, false);
+        let branch = nfa_1.state(branch.next_empty().unwrap());
+        check_status!(branch, End, 0, empty, false);
     }
 
     #[test]
@@ -646,34 +1228,30 @@ mod tests {
         // ((s)) --                                      -> ((e))
         //        \-empty->(empty) -b-> (empty) -empty-/
 
-        let status = RefCell::borrow(&nfa_1.start);
-        check_status!(status, Start, 2, EMPTY, true);
+        let status = nfa_1.state(nfa_1.start);
+        check_status!(status, Start, 2, empty, true);
 
-        let status_pair = status.status_set.get(0).unwrap();
-        assert!(status_pair.0.input(EMPTY));
-        let branch = RefCell::borrow(&status_pair.1);
+        let status_pair = status.transitions.first().unwrap();
+        assert!(status_pair.0.is_epsilon());
+        let branch = nfa_1.state(status_pair.1);
         check_status!(branch, Node, 1, BRANCH_1_TARGET, true);
 
-        let branch = branch.next(BRANCH_1_TARGET).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, Node, 1, EMPTY, true);
+        let branch = nfa_1.state(branch.next(BRANCH_1_TARGET).unwrap());
+        check_status!(branch, Node, 1, empty, true);
 
-        let branch = branch.next(EMPTY).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, End, 0, EMPTY, false);
+        let branch = nfa_1.state(branch.next_empty().unwrap());
+        check_status!(branch, End, 0, empty, false);
 
-        let status_pair = status.status_set.get(1).unwrap();
-        assert!(status_pair.0.input(EMPTY));
-        let branch = RefCell::borrow(&status_pair.1);
+        let status_pair = status.transitions.get(1).unwrap();
+        assert!(status_pair.0.is_epsilon());
+        let branch = nfa_1.state(status_pair.1);
         check_status!(branch, Node, 1, BRANCH_2_TARGET, true);
 
-        let branch = branch.next(BRANCH_2_TARGET).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, Node, 1, EMPTY, true);
+        let branch = nfa_1.state(branch.next(BRANCH_2_TARGET).unwrap());
+        check_status!(branch, Node, 1, empty, true);
 
-        let branch = branch.next(EMPTY).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, End, 0, EMPTY, false);
+        let branch = nfa_1.state(branch.next_empty().unwrap());
+        check_status!(branch, End, 0, empty, false);
     }
 
     #[test]
@@ -689,21 +1267,18 @@ mod tests {
         */
         nfa_1.and(nfa_2);
 
-        let status = RefCell::borrow(&nfa_1.start);
+        let status = nfa_1.state(nfa_1.start);
         check_status!(status, Start, 1, NFA_1_TEXT, true);
 
-        let status = status.next(NFA_1_TEXT).unwrap();
-        let status = RefCell::borrow(&status);
+        let status = nfa_1.state(status.next(NFA_1_TEXT).unwrap());
 
-        check_status!(status, Node, 1, EMPTY, true);
+        check_status!(status, Node, 1, empty, true);
 
-        let status = status.next(EMPTY).unwrap();
-        let status = RefCell::borrow(&status);
+        let status = nfa_1.state(status.next_empty().unwrap());
         check_status!(status, Node, 1, NFA_2_TEXT, true);
 
-        let status = status.next(NFA_2_TEXT).unwrap();
-        let status = RefCell::borrow(&status);
-        check_status!(status, End, 0, EMPTY, false);
+        let status = nfa_1.state(status.next(NFA_2_TEXT).unwrap());
+        check_status!(status, End, 0, empty, false);
     }
 
     #[test]
@@ -719,74 +1294,62 @@ mod tests {
         const TARGET_C: char = 'c';
         const TARGET_NOT_EXIST: char = '\0';
 
-        let start = Status::start();
-        let mut start = RefCell::borrow_mut(&start);
-
-        let status_1 = Status::node();
-        {
-            let end_1 = Status::end();
-            let mut status_1 = RefCell::borrow_mut(&status_1);
-            status_1.append_next(TARGET_A, end_1);
-        }
-
-        start.append_next(EMPTY, Rc::clone(&status_1));
+        let mut nfa = NFA::new();
+        let (start, end) = (nfa.start, nfa.end);
+        nfa.states[start].transitions.clear();
+        nfa.states
+            .extend([State::node(), State::node(), State::node(), State::node()]);
+        let (status_1, status_2, node_2, status_3) = (2, 3, 4, 5);
 
-        let status_2 = Status::node();
-        {
-            let end_2 = Status::end();
+        let rule = |text| Transition::Rule(SingleCharRule::boxed(text));
+        nfa.append(status_1, rule(TARGET_A), end);
+        nfa.append_empty(start, status_1);
 
-            let node_2 = Status::node();
-            {
-                let mut node_2_tem = RefCell::borrow_mut(&node_2);
-                node_2_tem.append_next(EMPTY, end_2);
-            }
-
-            let mut status_2 = RefCell::borrow_mut(&status_2);
-            status_2.append_next(TARGET_B, node_2);
-        }
-
-        start.append_next(EMPTY, status_2);
-
-        let status_3 = Status::node();
-        {
-            let end_3 = Status::end();
-            let mut status_3 = RefCell::borrow_mut(&status_3);
-            status_3.append_next(EMPTY, end_3);
-        }
+        nfa.append_empty(node_2, end);
+        nfa.append(status_2, rule(TARGET_B), node_2);
+        nfa.append_empty(start, status_2);
 
-        start.append_next(TARGET_C, status_3);
+        nfa.append_empty(status_3, end);
+        nfa.append(start, rule(TARGET_C), status_3);
 
         // action
-        let next = start.next_skip_empty(TARGET_A);
-        assert!(next.is_some());
-        let next = next.unwrap();
-        let next = RefCell::borrow(&next);
+        let next = nfa.next_skip_empty(start, TARGET_A);
+        assert_eq!(next, Some(end));
+        let next = nfa.state(next.unwrap());
 
-        check_status!(next, End, 0, EMPTY, false);
+        check_status!(next, End, 0, empty, false);
 
-        let next = start.next_skip_empty(TARGET_B);
-        assert!(next.is_some());
-        let next = next.unwrap();
-        let next = RefCell::borrow(&next);
+        let next = nfa.next_skip_empty(start, TARGET_B);
+        assert_eq!(next, Some(node_2));
+        let next = nfa.state(next.unwrap());
 
-        check_status!(next, Node, 1, EMPTY, true);
+        check_status!(next, Node, 1, empty, true);
 
-        let next = start.next_skip_empty(TARGET_C);
-        assert!(next.is_some());
-        let next = next.unwrap();
-        let next = RefCell::borrow(&next);
+        let next = nfa.next_skip_empty(start, TARGET_C);
+        assert_eq!(next, Some(status_3));
+        let next = nfa.state(next.unwrap());
 
-        check_status!(next, Node, 1, EMPTY, true);
+        check_status!(next, Node, 1, empty, true);
 
-        let next = start.next_skip_empty(TARGET_NOT_EXIST);
+        let next = nfa.next_skip_empty(start, TARGET_NOT_EXIST);
         assert!(next.is_none());
     }
 
+    #[test]
+    fn nul_is_not_empty() {
+        let nfa = NFA::with('\0');
+
+        let status = nfa.state(nfa.start);
+        check_status!(status, Start, 1, empty, false);
+        check_status!(status, Start, 1, '\0', true);
+        assert!(!nfa.is_end_skip_empty(nfa.start));
+    }
+
     #[test]
     fn with_alphanumeric_underline() {
         let nfa = NFA::with_alphanumeric_underline();
 
-        let status = RefCell::borrow(&nfa.start);
+        let status = nfa.state(nfa.start);
         check_status!(status, Start, 1, 'a', true);
         check_status!(status, Start, 1, 'A', true);
         check_status!(status, Start, 1, '0', true);
@@ -808,36 +1371,340 @@ mod tests {
         // ((s)) --                                            -> ((e))
         //        \-empty->(empty) -[a-zA-Z]-> (empty) -empty-/
 
-        let status = RefCell::borrow(&nfa.start);
-        check_status!(status, Start, 2, EMPTY, true);
+        let status = nfa.state(nfa.start);
+        check_status!(status, Start, 2, empty, true);
 
-        let status_pair = status.status_set.get(0).unwrap();
-        assert!(status_pair.0.input(EMPTY));
-        let branch = RefCell::borrow(&status_pair.1);
+        let status_pair = status.transitions.first().unwrap();
+        assert!(status_pair.0.is_epsilon());
+        let branch = nfa.state(status_pair.1);
         check_status!(branch, Node, 1, '_', true);
 
-        let branch = branch.next('_').unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, Node, 1, EMPTY, true);
+        let branch = nfa.state(branch.next('_').unwrap());
+        check_status!(branch, Node, 1, empty, true);
 
-        let branch = branch.next(EMPTY).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, End, 0, EMPTY, false);
+        let branch = nfa.state(branch.next_empty().unwrap());
+        check_status!(branch, End, 0, empty, false);
 
-        let status_pair = status.status_set.get(1).unwrap();
-        assert!(status_pair.0.input(EMPTY));
-        let branch = RefCell::borrow(&status_pair.1);
+        let status_pair = status.transitions.get(1).unwrap();
+        assert!(status_pair.0.is_epsilon());
+        let branch = nfa.state(status_pair.1);
         check_status!(branch, Node, 1, 'a', true);
         check_status!(branch, Node, 1, 'A', true);
         check_status!(branch, Node, 1, '0', false);
         check_status!(branch, Node, 1, '_', false);
 
-        let branch = branch.next('a').unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, Node, 1, EMPTY, true);
+        let branch = nfa.state(branch.next('a').unwrap());
+        check_status!(branch, Node, 1, empty, true);
+
+        let branch = nfa.state(branch.next_empty().unwrap());
+        check_status!(branch, End, 0, empty, false);
+    }
+
+    #[test]
+    fn star_nfa() {
+        let nfa = NFA::with('a').star();
+
+        // ((s)) -empty-> (empty) -a-> (empty) -empty-> ((e))
+        //   \----------------------empty---------------/^
+        let status = nfa.state(nfa.start);
+        check_status!(status, Start, 2, empty, true);
+        assert!(nfa.is_end_skip_empty(nfa.start));
+
+        let inner = nfa.state(status.next_empty().unwrap());
+        check_status!(inner, Node, 1, 'a', true);
+
+        let inner_end = inner.next('a').unwrap();
+        let status = nfa.state(inner_end);
+        check_status!(status, Node, 2, empty, true);
+        assert!(nfa.next_skip_empty(inner_end, 'a').is_some());
+        assert!(nfa.is_end_skip_empty(inner_end));
+    }
+
+    #[test]
+    fn plus_nfa() {
+        let nfa = NFA::with('a').plus();
+
+        // ((s)) -empty-> (empty) -a-> (empty) -empty-> ((e))
+        let status = nfa.state(nfa.start);
+        check_status!(status, Start, 1, empty, true);
+        assert!(!nfa.is_end_skip_empty(nfa.start));
+
+        let inner_end = nfa.next_skip_empty(nfa.start, 'a').unwrap();
+        let status = nfa.state(inner_end);
+        check_status!(status, Node, 2, empty, true);
+        assert!(nfa.next_skip_empty(inner_end, 'a').is_some());
+        assert!(nfa.is_end_skip_empty(inner_end));
+    }
+
+    #[test]
+    fn optional_nfa() {
+        let nfa = NFA::with('a').optional();
+
+        let status = nfa.state(nfa.start);
+        check_status!(status, Start, 2, empty, true);
+        assert!(nfa.is_end_skip_empty(nfa.start));
+
+        let inner_end = nfa.next_skip_empty(nfa.start, 'a').unwrap();
+        let status = nfa.state(inner_end);
+        check_status!(status, Node, 1, empty, true);
+        assert!(nfa.next_skip_empty(inner_end, 'a').is_none());
+        assert!(nfa.is_end_skip_empty(inner_end));
+    }
+
+    #[test]
+    fn star_of_star_does_not_loop() {
+        let nfa = NFA::with('a').star().star();
+
+        assert!(nfa.next_skip_empty(nfa.start, 'b').is_none());
+        assert!(nfa.is_end_skip_empty(nfa.start));
+        assert_eq!(nfa.closure_s(nfa.start).len(), 5);
+    }
+
+    #[test]
+    fn clone_nfa_is_independent() {
+        let nfa = NFA::with('a').star();
+        let mut copy = nfa.clone();
+        assert_eq!(copy.states.len(), nfa.states.len());
+
+        copy.and(NFA::with('b'));
+        assert_eq!(nfa.states.len(), 4);
+        assert_eq!(nfa.state(nfa.end).get_type(), &End);
+
+        let status = nfa.state(nfa.start);
+        check_status!(status, Start, 2, empty, true);
+        assert!(nfa.next_skip_empty(nfa.start, 'a').is_some());
+        assert!(nfa.is_end_skip_empty(nfa.start));
+        assert!(!copy.is_end_skip_empty(copy.start));
+
+        let end = copy.state(copy.end);
+        check_status!(end, End, 0, empty, false);
+    }
+
+    #[test]
+    fn repeat_exactly() {
+        let nfa = NFA::with('a').repeat(2, Some(2));
+
+        assert!(!nfa.is_end_skip_empty(nfa.start));
+
+        let status = nfa.next_skip_empty(nfa.start, 'a').unwrap();
+        assert!(!nfa.is_end_skip_empty(status));
+
+        let status = nfa.next_skip_empty(status, 'a').unwrap();
+        assert!(nfa.is_end_skip_empty(status));
+        assert!(nfa.next_skip_empty(status, 'a').is_none());
+    }
+
+    #[test]
+    fn repeat_zero() {
+        let nfa = NFA::with('a').repeat(0, Some(0));
+
+        assert!(nfa.is_end_skip_empty(nfa.start));
+        assert!(nfa.next_skip_empty(nfa.start, 'a').is_none());
+    }
+
+    #[test]
+    fn ids_are_dense_and_the_same() {
+        let nfa = NFA::from_str("(a|b)*c").unwrap();
+        let again = NFA::from_str("(a|b)*c").unwrap();
+
+        assert_eq!(nfa.start, 0);
+        assert_eq!(nfa.state_count(), again.state_count());
+        for id in 0..nfa.state_count() {
+            let (status, same) = (nfa.state(id), again.state(id));
+            assert_eq!(status.get_type(), same.get_type());
+
+            let targets: Vec<_> = status.transitions.iter().map(|(_, next)| *next).collect();
+            let same: Vec<_> = same.transitions.iter().map(|(_, next)| *next).collect();
+            assert_eq!(targets, same);
+            assert!(targets.iter().all(|&next| next < nfa.state_count()));
+        }
+    }
+
+    #[test]
+    fn nfa_is_send_sync_clone() {
+        fn check<T: Send + Sync + Clone>() {}
+        check::<NFA>();
+    }
+
+    #[test]
+    fn to_dot() {
+        let dot = NFA::with('a').to_dot();
+        assert_eq!(
+            dot,
+            "digraph NFA {
+    rankdir=LR;
+    node [shape=circle];
+    entry [shape=point];
+    entry -> 0;
+    0 [style=bold];
+    1 [shape=doublecircle];
+    0 -> 1 [label=\"a\"];
+}
+"
+        );
+
+        let dot = NFA::from_str(r"\b(a)").unwrap().to_dot();
+        for line in [
+            r#"    4 [label="4\nslot 2"];"#,
+            r#"    0 -> 1 [label="ε", style=dashed];"#,
+            r#"    2 -> 3 [label="\\b", style=dotted];"#,
+            r#"    6 -> 7 [label="a"];"#,
+        ] {
+            assert!(dot.contains(line), "{}", line);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn repeat_min_greater_than_max() {
+        NFA::with('a').repeat(3, Some(2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn repeat_over_the_limit() {
+        NFA::with('a').repeat(MAX_REPEAT + 1, None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_ast_over_the_limit() {
+        let repeat = |ast, count| Ast::Repeat {
+            ast: Box::new(ast),
+            min: count,
+            max: Some(count),
+        };
+        let ast = repeat(repeat(repeat(Ast::Literal('a'), 1000), 1000), 2);
+
+        NFA::from_ast(&ast);
+    }
+
+    #[test]
+    fn parse_error_kind_and_span() {
+        let cases = [
+            (r"a\qb", NFAErrorKind::UnknownEscape('q'), 1..3),
+            (r"ab\", NFAErrorKind::TrailingBackslash, 2..3),
+            (r"a[\q]", NFAErrorKind::UnknownEscape('q'), 2..4),
+            ("a[bc", NFAErrorKind::UnclosedBracket, 1..2),
+            (r"a[b\", NFAErrorKind::UnclosedBracket, 1..2),
+            ("x(ab", NFAErrorKind::UnclosedParenthesis, 1..2),
+            ("ab)c", NFAErrorKind::UnbalancedParenthesis, 2..3),
+            ("*a", NFAErrorKind::DanglingQuantifier, 0..1),
+            ("a|+", NFAErrorKind::DanglingQuantifier, 2..3),
+            ("({2})", NFAErrorKind::DanglingQuantifier, 1..4),
+            ("a[]", NFAErrorKind::EmptyClass, 1..3),
+            ("a{3,2}", NFAErrorKind::BadRange, 1..6),
+            ("a(?x)", NFAErrorKind::UnknownFlag('x'), 3..4),
+            ("a(?s-s-s:b)", NFAErrorKind::UnknownFlag('-'), 6..7),
+            ("a(?s", NFAErrorKind::UnclosedParenthesis, 1..2),
+            ("(?s:a", NFAErrorKind::UnclosedParenthesis, 0..1),
+            ("a(?<1st>b)", NFAErrorKind::InvalidGroupName, 4..7),
+            ("a(?<>b)", NFAErrorKind::InvalidGroupName, 4..4),
+            ("(?P<a-b>c)", NFAErrorKind::InvalidGroupName, 4..7),
+            ("a(?<name", NFAErrorKind::UnclosedParenthesis, 1..2),
+            (
+                "(?<x>a)(?<y>(?<x>b))",
+                NFAErrorKind::DuplicateGroupName("x".to_string()),
+                15..16,
+            ),
+            ("(?P)", NFAErrorKind::UnknownFlag('P'), 2..3),
+            ("a{1001}", NFAErrorKind::RepeatTooLarge, 1..7),
+            ("a{2,4000000000}", NFAErrorKind::RepeatTooLarge, 1..15),
+            (
+                "a{99999999999999999999999}",
+                NFAErrorKind::RepeatTooLarge,
+                1..26,
+            ),
+            ("x(a{1000}){1000}", NFAErrorKind::RepeatTooLarge, 10..16),
+            ("(a{1000}b){100,}", NFAErrorKind::RepeatTooLarge, 10..16),
+            (
+                "(a{1000}){100}(a{1000}){100}(a{1000}){100}(a{1000}){100}(a{1000}){100}",
+                NFAErrorKind::RepeatTooLarge,
+                0..70,
+            ),
+        ];
+
+        for (pattern, kind, span) in cases {
+            let err = NFA::from_str(pattern).err().unwrap();
+            assert_eq!(err.kind(), &kind, "{}", pattern);
+            assert_eq!(err.span(), span, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn parse_error_offset_is_byte_offset() {
+        let err = NFA::from_str(r"一\q").err().unwrap();
+
+        assert_eq!(err.offset(), 3);
+        assert_eq!(err.span(), 3..5);
+    }
+
+    #[test]
+    fn parse_error_render() {
+        const PATTERN: &str = r"一a\qb";
+        let err = NFA::from_str(PATTERN).err().unwrap();
+
+        assert_eq!(
+            err.render(PATTERN),
+            "unknown escape sequence `\\q` at 4\n一a\\qb\n  ^^"
+        );
+    }
+
+    #[test]
+    fn parse_error_render_at_end() {
+        const PATTERN: &str = "ab(";
+        let err = NFA::from_str(PATTERN).err().unwrap();
+
+        assert_eq!(err.to_string(), "unclosed parenthesis `(` at 2");
+        assert_eq!(
+            err.render(PATTERN),
+            "unclosed parenthesis `(` at 2\nab(\n  ^"
+        );
+    }
+
+    #[test]
+    fn class_is_single_transition() {
+        let nfa = NFA::from_str("[a-fx0-9]").unwrap();
+
+        let start = nfa.state(nfa.start);
+        for c in ['a', 'f', 'x', '0', '9'] {
+            check_status!(start, Start, 1, c, true);
+        }
+        for c in ['g', 'y', '-'] {
+            check_status!(start, Start, 1, c, false);
+        }
+    }
+
+    #[test]
+    fn parse_error_bad_class_range() {
+        let err = NFA::from_str("a[xz-a]").err().unwrap();
+
+        assert_eq!(err.kind(), &NFAErrorKind::BadRange);
+        assert_eq!(err.span(), 3..6);
+
+        let err = NFA::from_str("[^]").err().unwrap();
+        assert_eq!(err.kind(), &NFAErrorKind::EmptyClass);
+    }
+
+    #[test]
+    fn parse_error_invalid_escape() {
+        let cases = [
+            (r"a\xZ1", 1..3),
+            (r"a\x4", 1..4),
+            (r"\u41", 0..2),
+            (r"\u{110000}", 0..10),
+            (r"\u{D800}", 0..8),
+            (r"\u{}", 0..4),
+            (r"[\u{1234567}]", 1..12),
+        ];
+
+        for (pattern, span) in cases {
+            let err = NFA::from_str(pattern).err().unwrap();
+            assert_eq!(err.kind(), &NFAErrorKind::InvalidEscape, "{}", pattern);
+            assert_eq!(err.span(), span, "{}", pattern);
+        }
 
-        let branch = branch.next(EMPTY).unwrap();
-        let branch = RefCell::borrow(&branch);
-        check_status!(branch, End, 0, EMPTY, false);
+        let err = NFA::from_str(r"\q").err().unwrap();
+        assert_eq!(err.kind(), &NFAErrorKind::UnknownEscape('q'));
     }
 }
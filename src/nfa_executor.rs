@@ -1,6 +1,6 @@
-use std::{cell::RefCell, rc::Rc};
+use std::ops::{Index, Range};
 
-use crate::nfa::{StatusType, NFA};
+use crate::{nfa::NFA, pike_vm::PikeVM};
 
 pub struct NFAExecutor<T>
 where
@@ -18,23 +18,195 @@ where
         Self { nfa, text }
     }
 
+    /// whether NFA matches at the start of the trimmed text,
+    /// so a text with more behind a match is matched, see `NFA::is_full_match` to match the whole text
     pub fn test(&self) -> bool {
-        let mut status = self.nfa.get_start();
-
         let text = self.text.as_ref().trim();
-        for t in text.chars() {
-            let tem = Rc::clone(&status);
-            let status_ref = RefCell::borrow(&tem);
 
-            if let Some(next) = status_ref.next_skip_empty(t) {
-                status = Rc::clone(&next);
+        PikeVM::new(&self.nfa).search(text, 0, true).is_some()
+    }
+
+    /// every match in the text, see `NFA::find_iter`
+    pub fn find_iter(&self) -> FindMatches<'_, '_> {
+        self.nfa.find_iter(self.text.as_ref())
+    }
+}
+
+/// a match of NFA in the haystack, the offsets are in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'h> {
+    haystack: &'h str,
+    start: usize,
+    end: usize,
+}
+
+impl<'h> Match<'h> {
+    pub fn new(haystack: &'h str, start: usize, end: usize) -> Self {
+        Self {
+            haystack,
+            start,
+            end,
+        }
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// the matched text
+    pub fn as_str(&self) -> &'h str {
+        &self.haystack[self.range()]
+    }
+}
+
+/// the spans of the groups of a match, the group 0 is the whole match,
+/// a group not taking part in the match has no span
+#[derive(Clone)]
+pub struct Captures<'n, 'h> {
+    nfa: &'n NFA,
+    haystack: &'h str,
+    slots: Vec<Option<usize>>,
+}
+
+impl<'h> Captures<'_, 'h> {
+    /// the span of the group `index`
+    pub fn get(&self, index: usize) -> Option<Match<'h>> {
+        let start = (*self.slots.get(2 * index)?)?;
+        let end = self.slots[2 * index + 1]?;
+
+        Some(Match::new(self.haystack, start, end))
+    }
+
+    /// the span of the group named `name`
+    pub fn name(&self, name: &str) -> Option<Match<'h>> {
+        self.get(self.nfa.group_index(name)?)
+    }
+
+    /// the count of groups, including the group 0
+    pub fn group_len(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    /// the span of every group in order, starting with the group 0
+    pub fn iter(&self) -> impl Iterator<Item = Option<Match<'h>>> + '_ {
+        (0..self.group_len()).map(|index| self.get(index))
+    }
+}
+
+/// the text of the group `index`
+///
+/// # Panics
+/// if the group has no span
+impl Index<usize> for Captures<'_, '_> {
+    type Output = str;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index)
+            .map(|found| found.as_str())
+            .unwrap_or_else(|| panic!("no group at index {}", index))
+    }
+}
+
+/// runs every thread of NFA at once by `PikeVM`, so no path is missed
+impl NFA {
+    /// the leftmost match in the haystack, the longest one if several start there
+    pub fn find<'h>(&self, haystack: &'h str) -> Option<Match<'h>> {
+        self.find_at(haystack, 0)
+    }
+
+    /// the leftmost match starting at or after the byte offset `start`,
+    /// the offsets of the match are still of the whole haystack
+    ///
+    /// # Panics
+    /// if `start` is not on a char boundary of the haystack
+    pub fn find_at<'h>(&self, haystack: &'h str, start: usize) -> Option<Match<'h>> {
+        let slots = PikeVM::new(self).search(haystack, start, false)?;
+
+        Some(Match::new(haystack, slots[0]?, slots[1]?))
+    }
+
+    /// the spans of the groups of the leftmost match, the same match `find` gives
+    pub fn captures<'n, 'h>(&'n self, haystack: &'h str) -> Option<Captures<'n, 'h>> {
+        let slots = PikeVM::new(self).search(haystack, 0, false)?;
+
+        Some(Captures {
+            nfa: self,
+            haystack,
+            slots,
+        })
+    }
+
+    /// the successive non-overlapping matches in the haystack
+    pub fn find_iter<'n, 'h>(&'n self, haystack: &'h str) -> FindMatches<'n, 'h> {
+        FindMatches {
+            nfa: self,
+            haystack,
+            at: 0,
+            last_end: None,
+        }
+    }
+
+    /// whether the whole haystack is matched, nothing is trimmed
+    pub fn is_full_match(&self, haystack: &str) -> bool {
+        // the longest match at the start reaches the end if any does
+        PikeVM::new(self)
+            .search(haystack, 0, true)
+            .is_some_and(|slots| slots[1] == Some(haystack.len()))
+    }
+}
+
+/// the iterator of the successive non-overlapping matches, created by `NFA::find_iter`
+///
+/// an empty match moves the search on by one character,
+/// it's skipped if it's right at the end of the previous match
+pub struct FindMatches<'n, 'h> {
+    nfa: &'n NFA,
+    haystack: &'h str,
+    /// where the next search starts, always on a char boundary or past the end
+    at: usize,
+    last_end: Option<usize>,
+}
+
+impl<'h> Iterator for FindMatches<'_, 'h> {
+    type Item = Match<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.at > self.haystack.len() {
+                return None;
+            }
+
+            let found = self.nfa.find_at(self.haystack, self.at)?;
+            if found.is_empty() {
+                let next = self.haystack[found.end()..].chars().next();
+                self.at = found.end() + next.map_or(1, char::len_utf8);
+
+                if self.last_end == Some(found.end()) {
+                    continue;
+                }
             } else {
-                break;
+                self.at = found.end();
             }
-        }
 
-        let status = RefCell::borrow(&status);
-        status.get_type() == &StatusType::End
+            self.last_end = Some(found.end());
+            return Some(found);
+        }
     }
 }
 
@@ -42,9 +214,9 @@ where
 mod tests {
     use std::str::FromStr;
 
-    use crate::nfa::NFA;
+    use crate::nfa::{NFABuilder, NFA};
 
-    use super::NFAExecutor;
+    use super::{Captures, Match, NFAExecutor};
 
     #[test]
     fn nfa_executor_single_success() {
@@ -132,7 +304,7 @@ mod tests {
 
         let ne = NFAExecutor::new(nfa, TEST_SUCC);
 
-        assert_eq!(ne.test(), false);
+        assert!(!ne.test());
     }
 
     #[test]
@@ -155,7 +327,7 @@ mod tests {
         for i in TEST_SUCC_1 {
             let nfa = NFA::from_str(RULE).unwrap();
             let ne = NFAExecutor::new(nfa, i);
-            assert_eq!(ne.test(), false);
+            assert!(!ne.test());
         }
     }
 
@@ -170,4 +342,561 @@ mod tests {
             assert!(ne.test());
         }
     }
+
+    #[test]
+    fn nfa_executor_string_star_success() {
+        const RULE: &str = "ab*c";
+        const TEST_SUCC: [&str; 3] = ["ac", "abc", "abbbc"];
+
+        for i in TEST_SUCC {
+            let nfa = NFA::from_str(RULE).unwrap();
+            let ne = NFAExecutor::new(nfa, i);
+            assert!(ne.test());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_plus() {
+        const RULE: &str = "ab+c";
+
+        let nfa = NFA::from_str(RULE).unwrap();
+        assert!(!NFAExecutor::new(nfa, "ac").test());
+
+        for i in ["abc", "abbbc"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(NFAExecutor::new(nfa, i).test());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_optional() {
+        const RULE: &str = "ab?c";
+
+        for i in ["ac", "abc"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(NFAExecutor::new(nfa, i).test());
+        }
+
+        let nfa = NFA::from_str(RULE).unwrap();
+        assert!(!NFAExecutor::new(nfa, "abbc").test());
+    }
+
+    #[test]
+    fn nfa_executor_string_bracket_star() {
+        const RULE: &str = "[a-z]*";
+        const TEST_SUCC: [&str; 3] = ["", "a", "hello"];
+
+        for i in TEST_SUCC {
+            let nfa = NFA::from_str(RULE).unwrap();
+            let ne = NFAExecutor::new(nfa, i);
+            assert!(ne.test());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_dangling_quantifier() {
+        for rule in ["*", "+a", "?"] {
+            assert!(NFA::from_str(rule).is_err());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_counted_repetition() {
+        const RULE: &str = "[0-9]{1,3}";
+
+        for i in ["1", "12", "123"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(NFAExecutor::new(nfa, i).test());
+        }
+
+        let nfa = NFA::from_str(RULE).unwrap();
+        assert!(!NFAExecutor::new(nfa, "").test());
+    }
+
+    #[test]
+    fn nfa_executor_string_counted_repetition_unbounded() {
+        const RULE: &str = r"\w{2,}";
+
+        for i in ["ab", "a_0", "abcdefgh"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(NFAExecutor::new(nfa, i).test());
+        }
+
+        let nfa = NFA::from_str(RULE).unwrap();
+        assert!(!NFAExecutor::new(nfa, "a").test());
+    }
+
+    #[test]
+    fn nfa_executor_string_counted_repetition_exactly() {
+        const RULE: &str = "ab{3}c";
+
+        let nfa = NFA::from_str(RULE).unwrap();
+        assert!(NFAExecutor::new(nfa, "abbbc").test());
+
+        for i in ["abbc", "abbbbc"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(!NFAExecutor::new(nfa, i).test());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_brace_literal() {
+        const RULE: &str = "a{b}";
+
+        let nfa = NFA::from_str(RULE).unwrap();
+        assert!(NFAExecutor::new(nfa, "a{b}").test());
+    }
+
+    #[test]
+    fn nfa_executor_counted_repetition_min_greater_than_max() {
+        assert!(NFA::from_str("a{3,2}").is_err());
+        assert!(NFA::from_str("{2}").is_err());
+    }
+
+    #[test]
+    fn nfa_executor_string_alternation() {
+        const RULE: &str = "if|else|while";
+
+        for i in ["if", "else", "while"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(NFAExecutor::new(nfa, i).test());
+        }
+
+        for i in ["for", "whil"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(!NFAExecutor::new(nfa, i).test());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_group_alternation() {
+        const RULE: &str = "(ab|cd)e";
+
+        for i in ["abe", "cde"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(NFAExecutor::new(nfa, i).test());
+        }
+
+        for i in ["ae", "abde", "e"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(!NFAExecutor::new(nfa, i).test());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_group_quantifier() {
+        const RULE: &str = "(ab)+c";
+
+        for i in ["abc", "ababc"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(NFAExecutor::new(nfa, i).test());
+        }
+
+        for i in ["c", "abac"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(!NFAExecutor::new(nfa, i).test());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_unbalanced_parenthesis() {
+        for rule in ["(ab", "ab)", "(a|b))", "a(|b"] {
+            assert!(NFA::from_str(rule).is_err());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_bracket_any_range() {
+        const RULE: &str = "0x[a-fA-F0-9]+";
+
+        for i in ["0x1f", "0xDEADbeef"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(NFAExecutor::new(nfa, i).test());
+        }
+
+        for i in ["0xg", "0x"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(!NFAExecutor::new(nfa, i).test());
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_bracket_negated() {
+        const RULE: &str = "a[^0-9]b";
+
+        for i in ["axb", "a-b", "a一b"] {
+            let nfa = NFA::from_str(RULE).unwrap();
+            assert!(NFAExecutor::new(nfa, i).test());
+        }
+
+        let nfa = NFA::from_str(RULE).unwrap();
+        assert!(!NFAExecutor::new(nfa, "a5b").test());
+    }
+
+    #[test]
+    fn nfa_executor_string_bracket_literal_edges() {
+        const CASES: [(&str, &str); 5] = [
+            ("[-a]", "-"),
+            ("[a-]", "-"),
+            ("[]a]", "]"),
+            ("[^]a]", "b"),
+            ("[α-ω]", "β"),
+        ];
+
+        for (rule, text) in CASES {
+            let nfa = NFA::from_str(rule).unwrap();
+            assert!(NFAExecutor::new(nfa, text).test(), "{}", rule);
+        }
+
+        let nfa = NFA::from_str("[^]a]").unwrap();
+        assert!(!NFAExecutor::new(nfa, "]").test());
+    }
+
+    #[test]
+    fn nfa_executor_string_perl_classes() {
+        const CASES: [(&str, &str, bool); 10] = [
+            (r"\d+\.\d+", "3.14", true),
+            (r"\d+\.\d+", "3x14", false),
+            (r"a\sb", "a\tb", true),
+            (r"a\Sb", "a b", false),
+            (r"a\Sb", "a-b", true),
+            (r"a\Wb", "a_b", false),
+            (r"a\Wb", "a b", true),
+            (r"a\Db", "a1b", false),
+            (r"[\D]", "x", true),
+            (r"a[^\S]b", "a b", true),
+        ];
+
+        for (rule, text, expected) in CASES {
+            let nfa = NFA::from_str(rule).unwrap();
+            assert_eq!(NFAExecutor::new(nfa, text).test(), expected, "{}", rule);
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_char_escapes() {
+        const CASES: [(&str, &str); 7] = [
+            (r"\x41\u{4E00}", "A一"),
+            (r"a\nb", "a\nb"),
+            (r"a\tb\rc", "a\tb\rc"),
+            (r"\(\)\[\]\{\}", "()[]{}"),
+            (r"\.\*\+\?\|\^\$\\", r".*+?|^$\"),
+            (r"[\]\-\\]+", r"]-\"),
+            (r"[\x30-\x39]{2}", "42"),
+        ];
+
+        for (rule, text) in CASES {
+            let nfa = NFA::from_str(rule).unwrap();
+            assert!(NFAExecutor::new(nfa, text).test(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_dot() {
+        const CASES: [(&str, &str, bool); 8] = [
+            ("a.c", "abc", true),
+            ("a.c", "a一c", true),
+            ("a.c", "a\nc", false),
+            ("(?s)a.c", "a\nc", true),
+            ("a(?s:.)c", "a\nc", true),
+            ("(?s:a)(?-s)a.c", "aa\nc", false),
+            ("a((?s).)b|c", "a\nb", true),
+            (r"a\.c", "abc", false),
+        ];
+
+        for (rule, text, expected) in CASES {
+            let nfa = NFA::from_str(rule).unwrap();
+            assert_eq!(NFAExecutor::new(nfa, text).test(), expected, "{}", rule);
+        }
+    }
+
+    #[test]
+    fn nfa_executor_string_dot_matches_new_line() {
+        let nfa = NFABuilder::new()
+            .dot_matches_new_line(true)
+            .build("a.c")
+            .unwrap();
+        assert!(NFAExecutor::new(nfa, "a\nc").test());
+
+        let nfa = NFABuilder::new()
+            .dot_matches_new_line(true)
+            .build("a(?-s).c")
+            .unwrap();
+        assert!(!NFAExecutor::new(nfa, "a\nc").test());
+    }
+
+    #[test]
+    fn find_leftmost() {
+        let cases = [
+            ("abc", "xxabcxx", Some((2, 5))),
+            ("abc", "ab", None),
+            ("b+", "abbbcb", Some((1, 4))),
+            ("a|ab", "xab", Some((1, 3))),
+            ("a*", "bbb", Some((0, 0))),
+            (r"\d+", "abc 123 45", Some((4, 7))),
+            ("(ab|ac)d", "abacd", Some((2, 5))),
+            ("a*a", "baaa", Some((1, 4))),
+            ("c", "一二c", Some((6, 7))),
+            ("x?", "", Some((0, 0))),
+        ];
+
+        for (rule, haystack, expected) in cases {
+            let nfa = NFA::from_str(rule).unwrap();
+            let found = nfa.find(haystack).map(|m| (m.start(), m.end()));
+            assert_eq!(found, expected, "{} {}", rule, haystack);
+        }
+    }
+
+    #[test]
+    fn find_match() {
+        let nfa = NFA::from_str("[α-ω]+").unwrap();
+        let found = nfa.find("abγδε!").unwrap();
+
+        assert_eq!(found, Match::new("abγδε!", 2, 8));
+        assert_eq!(found.as_str(), "γδε");
+        assert_eq!(found.range(), 2..8);
+        assert_eq!(found.len(), 6);
+        assert!(!found.is_empty());
+    }
+
+    #[test]
+    fn is_full_match() {
+        const CASES: [(&str, &str, bool); 8] = [
+            ("abc", "abc", true),
+            ("abc", "abcd", false),
+            ("abc", " abc ", false),
+            ("a*a", "aaa", true),
+            ("(ab|ac)d", "acd", true),
+            ("", "", true),
+            (r"\s*", " \t\n", true),
+            ("a+", "", false),
+        ];
+
+        for (rule, haystack, expected) in CASES {
+            let nfa = NFA::from_str(rule).unwrap();
+            assert_eq!(nfa.is_full_match(haystack), expected, "{}", rule);
+        }
+    }
+
+    #[test]
+    fn find_iter() {
+        let cases = [
+            ("ab", "abxabab", vec![(0, 2), (3, 5), (5, 7)]),
+            (r"\d+", "a1b22c333", vec![(1, 2), (3, 5), (6, 9)]),
+            ("x", "abc", vec![]),
+            ("a*", "baaac", vec![(0, 0), (1, 4), (5, 5)]),
+            ("", "一二", vec![(0, 0), (3, 3), (6, 6)]),
+            ("a?", "一a", vec![(0, 0), (3, 4)]),
+            ("[α-ω]", "αbβ", vec![(0, 2), (3, 5)]),
+        ];
+
+        for (rule, haystack, expected) in cases {
+            let nfa = NFA::from_str(rule).unwrap();
+            let found: Vec<_> = nfa
+                .find_iter(haystack)
+                .map(|m| (m.start(), m.end()))
+                .collect();
+            assert_eq!(found, expected, "{} {}", rule, haystack);
+        }
+    }
+
+    #[test]
+    fn executor_find_iter() {
+        let nfa = NFA::from_str(r"(ERROR|WARN) \w+").unwrap();
+        let ne = NFAExecutor::new(nfa, "INFO a\nERROR disk\nWARN net\n");
+
+        let found: Vec<_> = ne.find_iter().map(|m| m.as_str()).collect();
+        assert_eq!(found, ["ERROR disk", "WARN net"]);
+    }
+
+    #[test]
+    fn find_at() {
+        let nfa = NFA::from_str("a").unwrap();
+
+        assert_eq!(nfa.find_at("aba", 1).map(|m| m.range()), Some(2..3));
+        assert_eq!(nfa.find_at("aba", 3), None);
+    }
+
+    #[test]
+    fn nfa_executor_every_path() {
+        const CASES: [(&str, &str); 4] = [
+            ("(ab|ac)d", "acd"),
+            ("a*a", "aaa"),
+            ("(a|ab)(c|bcd)", "abcd"),
+            (r"\w*\d", "abc1"),
+        ];
+
+        for (rule, text) in CASES {
+            let nfa = NFA::from_str(rule).unwrap();
+            assert!(NFAExecutor::new(nfa, text).test(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn captures() {
+        let nfa = NFA::from_str(r"(\d+)\.(\d+)").unwrap();
+        assert_eq!(nfa.groups(), 2);
+
+        let caps = nfa.captures("pi is 3.14!").unwrap();
+        assert_eq!(caps.group_len(), 3);
+        assert_eq!(&caps[0], "3.14");
+        assert_eq!(&caps[1], "3");
+        assert_eq!(&caps[2], "14");
+        assert_eq!(caps.get(2).map(|m| m.range()), Some(8..10));
+        assert_eq!(caps.get(3), None);
+    }
+
+    #[test]
+    fn captures_group_numbering() {
+        let nfa = NFA::from_str("((a)|(b))+(c)?").unwrap();
+        assert_eq!(nfa.groups(), 4);
+
+        let caps = nfa.captures("xab").unwrap();
+        let spans: Vec<_> = caps.iter().map(|m| m.map(|m| m.range())).collect();
+        // the last iteration of the repeated group wins, `(a)` keeps its span from the first one
+        assert_eq!(
+            spans,
+            [Some(1..3), Some(2..3), Some(1..2), Some(2..3), None]
+        );
+    }
+
+    #[test]
+    fn captures_nothing() {
+        let nfa = NFA::from_str("(a)b").unwrap();
+
+        assert!(nfa.captures("ac").is_none());
+    }
+
+    #[test]
+    fn captures_same_as_find() {
+        const CASES: [(&str, &str); 5] = [
+            ("(a|ab)(c|bcd)(d*)", "xabcd"),
+            ("(a*)(a)", "baaa"),
+            ("(x)?y", "y"),
+            ("()", "abc"),
+            (r"(\w+)@(\w+)\.com", "to: me@mail.com"),
+        ];
+
+        for (rule, haystack) in CASES {
+            let nfa = NFA::from_str(rule).unwrap();
+            let caps: Captures = nfa.captures(haystack).unwrap();
+            assert_eq!(caps.get(0), nfa.find(haystack), "{}", rule);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "no group at index 1")]
+    fn captures_index_missing_group() {
+        let nfa = NFA::from_str("(x)?y").unwrap();
+        let caps = nfa.captures("y").unwrap();
+
+        let _ = &caps[1];
+    }
+
+    #[test]
+    fn captures_name() {
+        let nfa = NFA::from_str(r"(?<int>[0-9]+)\.(?<frac>[0-9]+)").unwrap();
+        let caps = nfa.captures("x = 12.50;").unwrap();
+
+        assert_eq!(caps.name("int").map(|m| m.as_str()), Some("12"));
+        assert_eq!(caps.name("frac").map(|m| m.range()), Some(7..9));
+        assert_eq!(caps.name("exp"), None);
+        assert_eq!(&caps[2], "50");
+    }
+
+    #[test]
+    fn group_names() {
+        let nfa = NFA::from_str("(?<a>x)(y)(?:z)(?P<b>(w))").unwrap();
+
+        assert_eq!(nfa.groups(), 4);
+        assert_eq!(
+            nfa.group_names().collect::<Vec<_>>(),
+            [None, Some("a"), None, Some("b"), None]
+        );
+        assert_eq!(nfa.group_index("b"), Some(3));
+        assert_eq!(nfa.group_index("c"), None);
+    }
+
+    #[test]
+    fn non_capturing_group() {
+        let nfa = NFA::from_str("(?:ab)+(c)").unwrap();
+        assert_eq!(nfa.groups(), 1);
+
+        let caps = nfa.captures("ababc").unwrap();
+        assert_eq!(&caps[0], "ababc");
+        assert_eq!(&caps[1], "c");
+    }
+
+    #[test]
+    fn find_anchors() {
+        let cases = [
+            ("^a", "aba", vec![(0, 1)]),
+            ("a$", "aba", vec![(2, 3)]),
+            (r"\Aa|a\z", "aba", vec![(0, 1), (2, 3)]),
+            ("(?m)^\\w+", "ab cd\nef", vec![(0, 2), (6, 8)]),
+            ("(?m)\\w+$", "ab cd\nef", vec![(3, 5), (6, 8)]),
+            ("(?m)^", "a\nb", vec![(0, 0), (2, 2)]),
+            ("$", "ab", vec![(2, 2)]),
+        ];
+
+        for (rule, haystack, expected) in cases {
+            let nfa = NFA::from_str(rule).unwrap();
+            let found: Vec<_> = nfa
+                .find_iter(haystack)
+                .map(|m| (m.start(), m.end()))
+                .collect();
+            assert_eq!(found, expected, "{} {}", rule, haystack);
+        }
+    }
+
+    #[test]
+    fn multi_line_builder() {
+        let nfa = NFABuilder::new().multi_line(true).build("^b$").unwrap();
+        assert_eq!(nfa.find("a\nb\nc").map(|m| m.range()), Some(2..3));
+
+        let nfa = NFABuilder::new().multi_line(true).build(r"\Ab").unwrap();
+        assert_eq!(nfa.find("a\nb"), None);
+    }
+
+    #[test]
+    fn find_nul() {
+        let nfa = NFA::from_str(r"\x00+").unwrap();
+        assert_eq!(nfa.find("ab\0\0c").map(|m| m.range()), Some(2..4));
+        assert_eq!(nfa.find("abc"), None);
+
+        let nfa = NFA::from_str("[^a]").unwrap();
+        assert_eq!(nfa.find("a\0").map(|m| m.range()), Some(1..2));
+    }
+
+    #[test]
+    fn find_word_boundaries() {
+        let cases = [
+            (r"\bif\b", "if x; elif (if)", vec![(0, 2), (12, 14)]),
+            (r"\Bf\B", "if fff f", vec![(4, 5)]),
+            (r"\b", "ab c", vec![(0, 0), (2, 2), (3, 3), (4, 4)]),
+            (r"\B", "", vec![(0, 0)]),
+            (r"\b\w+\b", "été ok", vec![(2, 3), (6, 8)]),
+            (r"(?u)\b\w+\b", "été ok", vec![(6, 8)]),
+            (r"(?u:\b)é", "été", vec![(0, 2)]),
+        ];
+
+        for (rule, haystack, expected) in cases {
+            let nfa = NFA::from_str(rule).unwrap();
+            let found: Vec<_> = nfa
+                .find_iter(haystack)
+                .map(|m| (m.start(), m.end()))
+                .collect();
+            assert_eq!(found, expected, "{} {}", rule, haystack);
+        }
+    }
+
+    #[test]
+    fn unicode_word_builder() {
+        let nfa = NFABuilder::new().unicode_word(true).build(r"\bé").unwrap();
+        assert_eq!(nfa.find("été").map(|m| m.range()), Some(0..2));
+
+        // `é` is not a word character of `\w`
+        let nfa = NFABuilder::new().build(r"\bé").unwrap();
+        assert_eq!(nfa.find("été").map(|m| m.range()), Some(3..5));
+    }
 }
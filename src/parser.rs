@@ -0,0 +1,572 @@
+use crate::{
+    ast::{Assertion, Ast, Class, ClassItem, Flags, PerlClass},
+    nfa::{NFAError, NFAErrorKind, MAX_EXPANDED_LEN, MAX_REPEAT},
+};
+
+/// chars of a pattern, which knows the byte offset of the next char
+#[derive(Clone)]
+struct PatternChars<'a> {
+    pattern: &'a str,
+    offset: usize,
+    /// the names of the groups passed, a name can't be used twice
+    group_names: Vec<&'a str>,
+}
+
+impl<'a> PatternChars<'a> {
+    fn new(pattern: &'a str) -> Self {
+        Self {
+            pattern,
+            offset: 0,
+            group_names: Vec::new(),
+        }
+    }
+
+    /// byte offset of the next char
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.pattern[self.offset..].chars().next()
+    }
+
+    fn next_if(&mut self, func: impl FnOnce(&char) -> bool) -> Option<char> {
+        match self.peek() {
+            Some(c) if func(&c) => self.next(),
+            _ => None,
+        }
+    }
+
+    fn next_if_eq(&mut self, expected: char) -> Option<char> {
+        self.next_if(|c| *c == expected)
+    }
+}
+
+impl Iterator for PatternChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+}
+
+/// parses the pattern into ast
+pub(crate) fn parse(pattern: &str, flags: Flags) -> Result<Ast, NFAError> {
+    let mut chars = PatternChars::new(pattern);
+
+    let ast = parse_alternation(&mut chars, flags)?;
+    if chars.peek().is_some() {
+        // only an unbalanced `)` can stop the alternation before the end
+        return Err(NFAError::new(
+            NFAErrorKind::UnbalancedParenthesis,
+            chars.offset(),
+            1,
+        ));
+    }
+    // each repetition is checked as it's parsed, but their concatenations can still add up
+    if ast.expanded_len() > MAX_EXPANDED_LEN {
+        return Err(NFAError::new(
+            NFAErrorKind::RepeatTooLarge,
+            0,
+            pattern.len(),
+        ));
+    }
+
+    Ok(ast)
+}
+
+// the grammar of pattern, from the lowest precedence to the highest
+//
+// alternation   := concatenation ('|' concatenation)*
+// concatenation := repetition*
+// repetition    := atom ('*' | '+' | '?' | '{m}' | '{m,}' | '{m,n}')*
+// atom          := '(' alternation ')' | '(?<' name '>' alternation ')'
+//                | '(?' flags ':' alternation ')' | '[' class ']' | '\' escape | '.' | '^' | '$'
+//                | char
+//
+// inline flags `(?flags)` may stand between repetitions, changing the rest of the group
+
+/// `flags` is owned by the alternation, since inline flags last until the end of the group
+fn parse_alternation(chars: &mut PatternChars, mut flags: Flags) -> Result<Ast, NFAError> {
+    let mut asts = vec![parse_concatenation(chars, &mut flags)?];
+
+    while chars.next_if_eq('|').is_some() {
+        asts.push(parse_concatenation(chars, &mut flags)?);
+    }
+
+    if asts.len() == 1 {
+        return Ok(asts.pop().unwrap());
+    }
+    Ok(Ast::Alternation(asts))
+}
+
+fn parse_concatenation(chars: &mut PatternChars, flags: &mut Flags) -> Result<Ast, NFAError> {
+    let mut asts = Vec::new();
+
+    while let Some(c) = chars.peek() {
+        if c == '|' || c == ')' {
+            break;
+        }
+
+        let mut lookahead = chars.clone();
+        if let Some((inline, false)) = handle_flags(&mut lookahead, *flags)? {
+            *chars = lookahead;
+            *flags = inline;
+            continue;
+        }
+
+        asts.push(parse_repetition(chars, *flags)?);
+    }
+
+    match asts.len() {
+        0 => Ok(Ast::Empty),
+        1 => Ok(asts.pop().unwrap()),
+        _ => Ok(Ast::Concat(asts)),
+    }
+}
+
+fn parse_repetition(chars: &mut PatternChars, flags: Flags) -> Result<Ast, NFAError> {
+    let mut ast = parse_atom(chars, flags)?;
+
+    loop {
+        let offset = chars.offset();
+        let (min, max) = match chars.peek() {
+            Some('{') => match handle_brace(chars)? {
+                Some(repetition) => repetition,
+                None => break,
+            },
+            Some(c @ ('*' | '+' | '?')) => {
+                chars.next();
+                match c {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                }
+            }
+            _ => break,
+        };
+
+        ast = Ast::Repeat {
+            ast: Box::new(ast),
+            min,
+            max,
+        };
+        if ast.expanded_len() > MAX_EXPANDED_LEN {
+            return Err(NFAError::new(
+                NFAErrorKind::RepeatTooLarge,
+                offset,
+                chars.offset() - offset,
+            ));
+        }
+    }
+
+    Ok(ast)
+}
+
+fn parse_atom(chars: &mut PatternChars, flags: Flags) -> Result<Ast, NFAError> {
+    let offset = chars.offset();
+
+    let ast = match chars.peek() {
+        Some('(') => {
+            if let Some(name) = handle_group_name(chars)? {
+                let ast = parse_alternation(chars, flags)?;
+                if chars.next_if_eq(')').is_none() {
+                    return Err(NFAError::new(NFAErrorKind::UnclosedParenthesis, offset, 1));
+                }
+
+                return Ok(Ast::NamedGroup {
+                    name: name.to_string(),
+                    ast: Box::new(ast),
+                });
+            }
+
+            let scoped = match handle_flags(chars, flags)? {
+                Some((scoped, true)) => Some(scoped),
+                Some((_, false)) => unreachable!("inline flags are handled by concatenation"),
+                None => {
+                    chars.next();
+                    None
+                }
+            };
+
+            let ast = parse_alternation(chars, scoped.unwrap_or(flags))?;
+            if chars.next_if_eq(')').is_none() {
+                return Err(NFAError::new(NFAErrorKind::UnclosedParenthesis, offset, 1));
+            }
+
+            match scoped {
+                // the group is non-capturing, it only scopes the flags
+                Some(_) => ast,
+                None => Ast::Group(Box::new(ast)),
+            }
+        }
+        Some('.') => {
+            chars.next();
+            Ast::Any {
+                new_line: flags.dot_matches_new_line,
+            }
+        }
+        Some('^') => {
+            chars.next();
+            match flags.multi_line {
+                true => Ast::Assertion(Assertion::StartLine),
+                false => Ast::Assertion(Assertion::StartText),
+            }
+        }
+        Some('$') => {
+            chars.next();
+            match flags.multi_line {
+                true => Ast::Assertion(Assertion::EndLine),
+                false => Ast::Assertion(Assertion::EndText),
+            }
+        }
+        Some('*' | '+' | '?') => {
+            return Err(NFAError::new(NFAErrorKind::DanglingQuantifier, offset, 1))
+        }
+        Some('{') => {
+            let mut lookahead = chars.clone();
+            if handle_brace(&mut lookahead)?.is_some() {
+                return Err(NFAError::new(
+                    NFAErrorKind::DanglingQuantifier,
+                    offset,
+                    lookahead.offset() - offset,
+                ));
+            }
+            chars.next();
+            Ast::Literal('{')
+        }
+        Some('\\') => {
+            chars.next();
+            handle_backslash(chars, flags)?
+        }
+        Some('[') => {
+            chars.next();
+            handle_bracket(chars)?
+        }
+        Some(c) => {
+            chars.next();
+            Ast::Literal(c)
+        }
+        None => Ast::Empty,
+    };
+
+    Ok(ast)
+}
+
+/// handle flags symbol, `(?s)` or `(?sm:`, `-` turns off the flags after it, like `(?-s)`
+///
+/// # Return
+/// the flags after change, and whether it starts a group scoping the flags,
+/// `None` if it's not flags, the chars are not consumed in this case
+fn handle_flags(chars: &mut PatternChars, flags: Flags) -> Result<Option<(Flags, bool)>, NFAError> {
+    let offset = chars.offset();
+    let mut lookahead = chars.clone();
+    if lookahead.next_if_eq('(').is_none() || lookahead.next_if_eq('?').is_none() {
+        return Ok(None);
+    }
+
+    // a named group, like `(?<name>` or `(?P<name>`
+    let mut name = lookahead.clone();
+    name.next_if_eq('P');
+    if name.peek() == Some('<') {
+        return Ok(None);
+    }
+
+    let mut flags = flags;
+    let mut enabled = true;
+    let scoped = loop {
+        let flag_offset = lookahead.offset();
+        match lookahead.next() {
+            Some(')') => break false,
+            Some(':') => break true,
+            Some('-') if enabled => enabled = false,
+            Some('s') => flags.dot_matches_new_line = enabled,
+            Some('m') => flags.multi_line = enabled,
+            Some('u') => flags.unicode_word = enabled,
+            Some(c) => {
+                return Err(NFAError::new(
+                    NFAErrorKind::UnknownFlag(c),
+                    flag_offset,
+                    c.len_utf8(),
+                ))
+            }
+            None => return Err(NFAError::new(NFAErrorKind::UnclosedParenthesis, offset, 1)),
+        }
+    };
+
+    *chars = lookahead;
+    Ok(Some((flags, scoped)))
+}
+
+/// handle name symbol of named group, `(?<name>` or `(?P<name>`,
+/// the name is made of letters, digits and underlines, and can't start with a digit
+///
+/// # Return
+/// the name, `None` if it's not a named group, the chars are not consumed in this case
+fn handle_group_name<'a>(chars: &mut PatternChars<'a>) -> Result<Option<&'a str>, NFAError> {
+    let offset = chars.offset();
+    let mut lookahead = chars.clone();
+    if lookahead.next_if_eq('(').is_none() || lookahead.next_if_eq('?').is_none() {
+        return Ok(None);
+    }
+    lookahead.next_if_eq('P');
+    if lookahead.next_if_eq('<').is_none() {
+        return Ok(None);
+    }
+
+    let name_offset = lookahead.offset();
+    while lookahead.next_if(|c| *c != '>').is_some() {}
+    let name = &chars.pattern[name_offset..lookahead.offset()];
+    if lookahead.next_if_eq('>').is_none() {
+        return Err(NFAError::new(NFAErrorKind::UnclosedParenthesis, offset, 1));
+    }
+
+    let valid = name
+        .chars()
+        .enumerate()
+        .all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+    if name.is_empty() || !valid {
+        return Err(NFAError::new(
+            NFAErrorKind::InvalidGroupName,
+            name_offset,
+            name.len(),
+        ));
+    }
+    if lookahead.group_names.contains(&name) {
+        return Err(NFAError::new(
+            NFAErrorKind::DuplicateGroupName(name.to_string()),
+            name_offset,
+            name.len(),
+        ));
+    }
+
+    lookahead.group_names.push(name);
+    *chars = lookahead;
+    Ok(Some(name))
+}
+
+/// handle brace symbol of counted repetition
+/// {m}, {m,}, {m,n}
+///
+/// # Return
+/// `None` if the brace does not start a counted repetition, it's a literal `{` then,
+/// the chars are not consumed in this case
+fn handle_brace(chars: &mut PatternChars) -> Result<Option<(usize, Option<usize>)>, NFAError> {
+    fn number(chars: &mut PatternChars) -> Option<usize> {
+        let mut digits = String::new();
+        while let Some(c) = chars.next_if(char::is_ascii_digit) {
+            digits.push(c);
+        }
+        // a number overflowing is too large anyway
+        (!digits.is_empty()).then(|| digits.parse().unwrap_or(usize::MAX))
+    }
+
+    let offset = chars.offset();
+    let mut lookahead = chars.clone();
+    if lookahead.next_if_eq('{').is_none() {
+        return Ok(None);
+    }
+
+    let Some(min) = number(&mut lookahead) else {
+        return Ok(None);
+    };
+    let max = match lookahead.next() {
+        Some('}') => Some(min),
+        Some(',') if lookahead.peek() == Some('}') => {
+            lookahead.next();
+            None
+        }
+        Some(',') => match (number(&mut lookahead), lookahead.next()) {
+            (Some(max), Some('}')) => Some(max),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    if let Some(max) = max {
+        if min > max {
+            return Err(NFAError::new(
+                NFAErrorKind::BadRange,
+                offset,
+                lookahead.offset() - offset,
+            ));
+        }
+    }
+    if max.unwrap_or(min) > MAX_REPEAT {
+        return Err(NFAError::new(
+            NFAErrorKind::RepeatTooLarge,
+            offset,
+            lookahead.offset() - offset,
+        ));
+    }
+
+    *chars = lookahead;
+    Ok(Some((min, max)))
+}
+
+/// handle backslash symbol outside brackets, the backslash itself is consumed already
+fn handle_backslash(chars: &mut PatternChars, flags: Flags) -> Result<Ast, NFAError> {
+    let offset = chars.offset() - 1;
+
+    // assertions are not allowed in classes, so they are not escapes
+    if chars.next_if_eq('A').is_some() {
+        return Ok(Ast::Assertion(Assertion::StartText));
+    }
+    if chars.next_if_eq('z').is_some() {
+        return Ok(Ast::Assertion(Assertion::EndText));
+    }
+    if chars.next_if_eq('b').is_some() {
+        let unicode = flags.unicode_word;
+        return Ok(Ast::Assertion(Assertion::WordBoundary { unicode }));
+    }
+    if chars.next_if_eq('B').is_some() {
+        let unicode = flags.unicode_word;
+        return Ok(Ast::Assertion(Assertion::NotWordBoundary { unicode }));
+    }
+
+    match handle_escape(chars)? {
+        Some(ClassItem::Perl(perl)) => Ok(Ast::Class(Class::Perl(perl))),
+        Some(ClassItem::Char(c)) => Ok(Ast::Literal(c)),
+        _ => Err(NFAError::new(NFAErrorKind::TrailingBackslash, offset, 1)),
+    }
+}
+
+/// handle bracket symbol, the `[` itself is consumed already
+/// [abc], [a-z], [^a-z], []a], [a-]
+///
+/// `^` at the start negates the class, `]` at the start and `-` at the start or the end are literal
+fn handle_bracket(chars: &mut PatternChars) -> Result<Ast, NFAError> {
+    let offset = chars.offset() - 1;
+
+    let negated = chars.next_if_eq('^').is_some();
+    let mut items = Vec::new();
+
+    loop {
+        let item_offset = chars.offset();
+        let from = match chars.next() {
+            None => return Err(NFAError::new(NFAErrorKind::UnclosedBracket, offset, 1)),
+            Some(']') if !items.is_empty() => break,
+            Some(']') if !chars.pattern[chars.offset()..].contains(']') => {
+                return Err(NFAError::new(
+                    NFAErrorKind::EmptyClass,
+                    offset,
+                    chars.offset() - offset,
+                ))
+            }
+            Some('\\') => match handle_escape(chars)? {
+                Some(ClassItem::Char(c)) => c,
+                Some(item) => {
+                    items.push(item);
+                    continue;
+                }
+                None => return Err(NFAError::new(NFAErrorKind::UnclosedBracket, offset, 1)),
+            },
+            Some(c) => c,
+        };
+
+        let mut lookahead = chars.clone();
+        if lookahead.next_if_eq('-').is_none() {
+            items.push(ClassItem::Char(from));
+            continue;
+        }
+
+        let to = match lookahead.next() {
+            Some('\\') => match handle_escape(&mut lookahead)? {
+                Some(ClassItem::Char(c)) => c,
+                // `-` before an escaped class, like `[a-\d]`, is literal
+                _ => {
+                    items.push(ClassItem::Char(from));
+                    continue;
+                }
+            },
+            // `-` at the end, like `[a-]`, is literal
+            Some(']') | None => {
+                items.push(ClassItem::Char(from));
+                continue;
+            }
+            Some(c) => c,
+        };
+
+        if from > to {
+            return Err(NFAError::new(
+                NFAErrorKind::BadRange,
+                item_offset,
+                lookahead.offset() - item_offset,
+            ));
+        }
+
+        *chars = lookahead;
+        items.push(ClassItem::Range(from, to));
+    }
+
+    Ok(Ast::Class(Class::Bracket { negated, items }))
+}
+
+/// handle escape sequence, the backslash itself is consumed already
+/// \w \W \d \D \s \S, \n \t \r, \xHH, \u{HHHHHH}, and any escaped ASCII punctuation like \. \[ \\
+///
+/// # Return
+/// `ClassItem::Char` for a single character, `ClassItem::Perl` for a class,
+/// `None` if the pattern ends after the backslash
+fn handle_escape(chars: &mut PatternChars) -> Result<Option<ClassItem>, NFAError> {
+    let offset = chars.offset() - 1;
+    let invalid = |chars: &PatternChars| {
+        NFAError::new(NFAErrorKind::InvalidEscape, offset, chars.offset() - offset)
+    };
+
+    let item = match chars.next() {
+        Some('w') => ClassItem::Perl(PerlClass::Word),
+        Some('W') => ClassItem::Perl(PerlClass::NotWord),
+        Some('d') => ClassItem::Perl(PerlClass::Digit),
+        Some('D') => ClassItem::Perl(PerlClass::NotDigit),
+        Some('s') => ClassItem::Perl(PerlClass::Space),
+        Some('S') => ClassItem::Perl(PerlClass::NotSpace),
+        Some('n') => ClassItem::Char('\n'),
+        Some('t') => ClassItem::Char('\t'),
+        Some('r') => ClassItem::Char('\r'),
+        Some('x') => {
+            let mut digits = String::new();
+            for _ in 0..2 {
+                match chars.next_if(char::is_ascii_hexdigit) {
+                    Some(c) => digits.push(c),
+                    None => return Err(invalid(chars)),
+                }
+            }
+
+            let code = u32::from_str_radix(&digits, 16).unwrap();
+            ClassItem::Char(char::from_u32(code).unwrap())
+        }
+        Some('u') => {
+            if chars.next_if_eq('{').is_none() {
+                return Err(invalid(chars));
+            }
+
+            let mut digits = String::new();
+            while let Some(c) = chars.next_if(char::is_ascii_hexdigit) {
+                digits.push(c);
+            }
+            if chars.next_if_eq('}').is_none() || digits.is_empty() || digits.len() > 6 {
+                return Err(invalid(chars));
+            }
+
+            let code = u32::from_str_radix(&digits, 16).unwrap();
+            match char::from_u32(code) {
+                Some(c) => ClassItem::Char(c),
+                None => return Err(invalid(chars)),
+            }
+        }
+        Some(c) if c.is_ascii_punctuation() => ClassItem::Char(c),
+        Some(c) => {
+            return Err(NFAError::new(
+                NFAErrorKind::UnknownEscape(c),
+                offset,
+                1 + c.len_utf8(),
+            ))
+        }
+        None => return Ok(None),
+    };
+
+    Ok(Some(item))
+}
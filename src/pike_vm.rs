@@ -0,0 +1,160 @@
+use crate::nfa::{Look, StateId, StatusType, NFA};
+
+/// the positions recorded by a thread, see `State::slot`,
+/// the slot 0 and 1 are the start and end of the whole match
+pub(crate) type Slots = Vec<Option<usize>>;
+
+struct Thread {
+    status: StateId,
+    slots: Slots,
+}
+
+/// runs every thread of NFA in lockstep over the haystack,
+/// a thread is a status with its own capture slots, two threads in the same status are never kept,
+/// so the time is linear to the haystack
+pub(crate) struct PikeVM<'n> {
+    nfa: &'n NFA,
+}
+
+impl<'n> PikeVM<'n> {
+    pub(crate) fn new(nfa: &'n NFA) -> Self {
+        Self { nfa }
+    }
+
+    /// the leftmost match starting at or after `start`, the longest one if several start there,
+    /// the match must start right at `start` if `anchored` is set
+    ///
+    /// the threads started earlier come first, and for the same start,
+    /// the thread following the earlier transitions comes first, which decides the slots of groups
+    pub(crate) fn search(&self, haystack: &str, start: usize, anchored: bool) -> Option<Slots> {
+        let slot_count = 2 * (self.nfa.groups() + 1);
+        let mut threads = Vec::new();
+        let mut seen = vec![false; self.nfa.state_count()];
+        let mut best: Option<Slots> = None;
+
+        let positions = haystack[start..]
+            .char_indices()
+            .map(|(i, c)| (start + i, Some(c)))
+            .chain([(haystack.len(), None)]);
+
+        for (at, c) in positions {
+            // no thread starting later can be more leftmost than the match found
+            if best.is_none() && (!anchored || at == start) {
+                let mut slots = vec![None; slot_count];
+                slots[0] = Some(at);
+                let position = Position::new(haystack, at);
+                add_thread(
+                    self.nfa,
+                    &mut threads,
+                    &mut seen,
+                    self.nfa.get_start(),
+                    slots,
+                    position,
+                );
+            }
+
+            for thread in threads.iter() {
+                if *self.nfa.state(thread.status).get_type() != StatusType::End {
+                    continue;
+                }
+
+                let better = best.as_ref().is_none_or(|best| {
+                    thread.slots[0] < best[0] || (thread.slots[0] == best[0] && Some(at) > best[1])
+                });
+                if better {
+                    let mut slots = thread.slots.clone();
+                    slots[1] = Some(at);
+                    best = Some(slots);
+                }
+            }
+
+            if let Some(best) = best.as_ref() {
+                threads.retain(|thread| thread.slots[0] <= best[0]);
+            }
+
+            let Some(c) = c else {
+                break;
+            };
+            if threads.is_empty() && (best.is_some() || anchored) {
+                break;
+            }
+
+            let position = Position::new(haystack, at + c.len_utf8());
+            let mut next_threads = Vec::with_capacity(threads.len());
+            seen.fill(false);
+
+            for thread in threads.drain(..) {
+                let status = self.nfa.state(thread.status);
+                for (transition, next) in status.transitions.iter() {
+                    if transition.accepts(c) {
+                        let slots = thread.slots.clone();
+                        add_thread(
+                            self.nfa,
+                            &mut next_threads,
+                            &mut seen,
+                            *next,
+                            slots,
+                            position,
+                        );
+                    }
+                }
+            }
+
+            threads = next_threads;
+        }
+
+        best
+    }
+}
+
+/// a position in the haystack, with what the assertions see around it
+#[derive(Clone, Copy)]
+struct Position {
+    at: usize,
+    before: Look,
+    after: Look,
+}
+
+impl Position {
+    fn new(haystack: &str, at: usize) -> Self {
+        Self {
+            at,
+            before: Look::of(haystack[..at].chars().next_back()),
+            after: Look::of(haystack[at..].chars().next()),
+        }
+    }
+}
+
+/// adds the thread and the threads following its empty transitions in order,
+/// and the transitions of the assertions holding at the position,
+/// the status already having a thread in `seen` is skipped
+fn add_thread(
+    nfa: &NFA,
+    threads: &mut Vec<Thread>,
+    seen: &mut [bool],
+    status: StateId,
+    slots: Slots,
+    position: Position,
+) {
+    let mut stack = vec![(status, slots)];
+
+    while let Some((status, mut slots)) = stack.pop() {
+        if std::mem::replace(&mut seen[status], true) {
+            continue;
+        }
+
+        let state = nfa.state(status);
+        if let Some(slot) = state.slot() {
+            slots[slot] = Some(position.at);
+        }
+
+        // pushed in reverse, so the earlier transition is followed first
+        for (transition, next) in state.transitions.iter().rev() {
+            if transition.is_passed(position.before, position.after) {
+                stack.push((*next, slots.clone()));
+            }
+        }
+
+        threads.push(Thread { status, slots });
+    }
+}
@@ -0,0 +1,702 @@
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+use crate::{
+    ast::Ast,
+    lexer::Lexer,
+    nfa::{NFABuilder, NFAError},
+};
+
+/// a lex specification, in the three sections of flex
+///
+/// ```text
+/// DIGIT    [0-9]
+/// %%
+/// {DIGIT}+    { return NUMBER; }
+/// "if"        { return IF; }
+/// [ \t\n]+    ;
+/// %%
+/// fn main() {}
+/// ```
+///
+/// the definitions name patterns to be referenced as `{DIGIT}`,
+/// the rules pair a pattern with its action, the user code is kept as it is
+///
+/// the patterns take the syntax of this crate, with `"..."` for the literal text and `{NAME}` for a definition
+#[derive(Debug, Clone, Default)]
+pub struct Spec {
+    /// the pattern of every definition, the references within it are already expanded
+    definitions: HashMap<String, String>,
+    rules: Vec<SpecRule>,
+    /// the code of the indented lines and `%{ ... %}` blocks before the first rule
+    code: String,
+    user_code: String,
+}
+
+/// a rule of the spec
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecRule {
+    /// the pattern in the syntax of this crate, the definitions and the quotes are expanded
+    pub pattern: String,
+    /// the action code, `|` is replaced by the action of the next rule
+    pub action: String,
+    /// the line of the rule in the spec, starting from 1
+    pub line: usize,
+}
+
+impl Spec {
+    pub fn parse(source: &str) -> Result<Self, SpecError> {
+        let mut lines = source.lines().enumerate().map(|(i, line)| (i + 1, line));
+        let mut spec = Spec::default();
+
+        // definitions
+        loop {
+            let Some((number, line)) = lines.next() else {
+                return Err(SpecError::new(
+                    SpecErrorKind::MissingRules,
+                    number_of(source),
+                ));
+            };
+
+            if line.starts_with("%%") {
+                break;
+            } else if line.starts_with("%{") {
+                spec.code_block(&mut lines, number)?;
+            } else if line.starts_with("/*") || line.trim().is_empty() {
+                continue;
+            } else if line.starts_with(char::is_whitespace) {
+                spec.push_code(line);
+            } else if line.starts_with('%') {
+                let directive = line.split_whitespace().next().unwrap_or(line);
+                return Err(SpecError::new(
+                    SpecErrorKind::Unsupported(directive.to_string()),
+                    number,
+                ));
+            } else {
+                spec.parse_definition(line, number)?;
+            }
+        }
+
+        // rules
+        let mut pending = Vec::new();
+        while let Some((number, line)) = lines.next() {
+            if line.starts_with("%%") {
+                break;
+            } else if line.starts_with("%{") {
+                spec.code_block(&mut lines, number)?;
+            } else if line.trim().is_empty() {
+                continue;
+            } else if line.starts_with(char::is_whitespace) {
+                spec.push_code(line);
+            } else {
+                let (pattern, rest) = spec.pattern(line, number)?;
+                let action = action(rest, &mut lines, number)?;
+
+                if action == "|" {
+                    pending.push((pattern, number));
+                    continue;
+                }
+
+                for (pattern, line) in pending.drain(..) {
+                    spec.rules.push(SpecRule {
+                        pattern,
+                        action: action.clone(),
+                        line,
+                    });
+                }
+                spec.rules.push(SpecRule {
+                    pattern,
+                    action,
+                    line: number,
+                });
+            }
+        }
+
+        if let Some((_, line)) = pending.first() {
+            return Err(SpecError::new(SpecErrorKind::DanglingBar, *line));
+        }
+
+        // user code
+        spec.user_code = lines.map(|(_, line)| format!("{}\n", line)).collect();
+
+        Ok(spec)
+    }
+
+    /// the pattern of the definition `name`, the references within it are expanded
+    pub fn definition(&self, name: &str) -> Option<&str> {
+        self.definitions.get(name).map(String::as_str)
+    }
+
+    pub fn rules(&self) -> &[SpecRule] {
+        &self.rules
+    }
+
+    /// the code of the indented lines and `%{ ... %}` blocks before the rules
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// the third section as it is, empty if there is none
+    pub fn user_code(&self) -> &str {
+        &self.user_code
+    }
+
+    /// builds the lexer of the rules, the token kind of each rule is its action,
+    /// `^` and `$` match at the start and the end of a line like flex
+    pub fn lexer(&self) -> Result<Lexer<String>, SpecError> {
+        let mut builder = NFABuilder::new();
+        builder.multi_line(true);
+
+        let mut nfa_list = Vec::with_capacity(self.rules.len());
+        for rule in self.rules.iter() {
+            let nfa = builder
+                .build(&rule.pattern)
+                .map_err(|error| SpecError::new(SpecErrorKind::Pattern(error), rule.line))?;
+            nfa_list.push(nfa);
+        }
+
+        let kinds = self.rules.iter().map(|rule| rule.action.clone()).collect();
+
+        Ok(Lexer::from_nfa_list(nfa_list, kinds))
+    }
+
+    fn push_code(&mut self, line: &str) {
+        self.code.push_str(line);
+        self.code.push('\n');
+    }
+
+    /// takes the lines up to `%}` as code, the line `%{` is already taken
+    fn code_block<'s>(
+        &mut self,
+        lines: &mut impl Iterator<Item = (usize, &'s str)>,
+        start: usize,
+    ) -> Result<(), SpecError> {
+        for (_, line) in lines {
+            if line.starts_with("%}") {
+                return Ok(());
+            }
+            self.push_code(line);
+        }
+
+        Err(SpecError::new(SpecErrorKind::UnclosedCode, start))
+    }
+
+    /// `NAME pattern`, like flex the pattern is the rest of the line,
+    /// whitespace within it is literal
+    fn parse_definition(&mut self, line: &str, number: usize) -> Result<(), SpecError> {
+        let name_len = line
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(line.len());
+        let (name, rest) = line.split_at(name_len);
+
+        if !is_name(name) || !rest.starts_with(char::is_whitespace) || rest.trim().is_empty() {
+            return Err(SpecError::new(SpecErrorKind::InvalidDefinition, number));
+        }
+
+        let mut rest = rest.trim();
+        let mut pattern = String::new();
+        while !rest.is_empty() {
+            let (part, after) = self.pattern(rest, number)?;
+            pattern.push_str(&part);
+
+            let space_len = after.len() - after.trim_start().len();
+            for c in after[..space_len].chars() {
+                pattern.push_str(&Ast::Literal(c).to_string());
+            }
+            rest = &after[space_len..];
+        }
+        self.definitions.insert(name.to_string(), pattern);
+
+        Ok(())
+    }
+
+    /// translates the pattern at the start of the text up to the first whitespace outside quotes and brackets
+    ///
+    /// # Return
+    /// the pattern in the syntax of this crate, and the rest of the text
+    fn pattern<'t>(&self, text: &'t str, number: usize) -> Result<(String, &'t str), SpecError> {
+        let unsupported =
+            |what: &str| SpecError::new(SpecErrorKind::Unsupported(what.to_string()), number);
+        if text.starts_with('<') {
+            return Err(unsupported("start condition"));
+        }
+
+        let mut pattern = String::new();
+        let mut chars = text.char_indices().peekable();
+
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() {
+                return Ok((pattern, &text[i..]));
+            }
+            chars.next();
+
+            match c {
+                '"' => {
+                    let mut quoted = String::new();
+                    let mut count = 0;
+                    loop {
+                        match chars.next() {
+                            None => {
+                                return Err(SpecError::new(SpecErrorKind::UnclosedQuote, number))
+                            }
+                            Some((_, '"')) => break,
+                            Some((_, '\\')) if chars.peek().is_some() => {
+                                let (_, c) = chars.next().unwrap();
+                                quoted.push_str(&Ast::Literal(c_escape(c)).to_string());
+                            }
+                            Some((_, c)) => quoted.push_str(&Ast::Literal(c).to_string()),
+                        }
+                        count += 1;
+                    }
+
+                    // grouped like a definition, so a quantifier after it takes the whole text
+                    if count > 1 {
+                        pattern.push_str("(?:");
+                        pattern.push_str(&quoted);
+                        pattern.push(')');
+                    } else {
+                        pattern.push_str(&quoted);
+                    }
+                }
+                '\\' => match chars.next() {
+                    Some((_, ' ')) => pattern.push(' '),
+                    Some((_, c)) => {
+                        pattern.push('\\');
+                        pattern.push(c);
+                    }
+                    None => pattern.push('\\'),
+                },
+                '[' => {
+                    // copied as it is up to the closing `]`, a leading `]` or `^]` is literal
+                    pattern.push('[');
+                    for leading in ['^', ']'] {
+                        if let Some((_, c)) = chars.next_if(|&(_, c)| c == leading) {
+                            pattern.push(c);
+                        }
+                    }
+                    while let Some((_, c)) = chars.next() {
+                        pattern.push(c);
+                        match c {
+                            ']' => break,
+                            '\\' => {
+                                if let Some((_, c)) = chars.next() {
+                                    pattern.push(c);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                '{' => {
+                    let rest = &text[i + 1..];
+                    let close = rest.find('}');
+                    match close.map(|close| &rest[..close]) {
+                        Some(name) if is_name(name) => {
+                            let definition = self.definition(name).ok_or_else(|| {
+                                SpecError::new(
+                                    SpecErrorKind::UndefinedName(name.to_string()),
+                                    number,
+                                )
+                            })?;
+                            pattern.push_str("(?:");
+                            pattern.push_str(definition);
+                            pattern.push(')');
+
+                            for _ in 0..name.chars().count() + 1 {
+                                chars.next();
+                            }
+                        }
+                        // a repetition like `{1,3}`
+                        _ => pattern.push('{'),
+                    }
+                }
+                '/' => return Err(unsupported("trailing context `/`")),
+                c => pattern.push(c),
+            }
+        }
+
+        Ok((pattern, ""))
+    }
+}
+
+/// whether the text is a name of definition, starting with a letter or an underline
+fn is_name(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// the character of the C escape `\c` within quotes, any other escaped character is itself
+fn c_escape(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        'f' => '\x0C',
+        'v' => '\x0B',
+        'a' => '\x07',
+        'b' => '\x08',
+        '0' => '\0',
+        c => c,
+    }
+}
+
+/// the line number right after the last line
+fn number_of(source: &str) -> usize {
+    source.lines().count() + 1
+}
+
+/// the action after the pattern, taking the following lines until the braces are balanced
+fn action<'s>(
+    rest: &str,
+    lines: &mut impl Iterator<Item = (usize, &'s str)>,
+    number: usize,
+) -> Result<String, SpecError> {
+    let mut action = rest.trim().to_string();
+    let mut depth = braces(&action);
+
+    while depth > 0 {
+        let Some((_, line)) = lines.next() else {
+            return Err(SpecError::new(SpecErrorKind::UnclosedAction, number));
+        };
+        action.push('\n');
+        action.push_str(line);
+        depth += braces(line);
+    }
+
+    Ok(action)
+}
+
+/// the count of `{` minus the count of `}` outside the string and char literals
+fn braces(code: &str) -> isize {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut chars = code.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '"') => quote = Some(c),
+            // a char literal, rather than a lifetime like `'a` which has no closing `'`
+            (None, '\'') if is_char_literal(chars.clone()) => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') => depth -= 1,
+            _ => {}
+        }
+    }
+
+    depth
+}
+
+/// whether the text after a `'` is the rest of a char literal like `a'` or `\n'`
+fn is_char_literal(mut chars: std::str::Chars) -> bool {
+    match chars.next() {
+        Some('\\') => true,
+        Some(_) => chars.next() == Some('\''),
+        None => false,
+    }
+}
+
+/// the error of parsing a spec, carrying the line where it happens, starting from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecError {
+    kind: SpecErrorKind,
+    line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecErrorKind {
+    /// the spec has no `%%` to start the rules
+    MissingRules,
+    /// definition which is not a name followed by a pattern
+    InvalidDefinition,
+    /// `{NAME}` without the definition before
+    UndefinedName(String),
+    /// `"` without its closing `"`
+    UnclosedQuote,
+    /// `%{` without its `%}`
+    UnclosedCode,
+    /// action whose `{` is not closed
+    UnclosedAction,
+    /// `|` as the action of the last rule, there's no next action to share
+    DanglingBar,
+    /// flex feature which is not supported, like `%option` or trailing context
+    Unsupported(String),
+    /// the pattern of the rule is wrong
+    Pattern(NFAError),
+}
+
+impl SpecError {
+    pub fn new(kind: SpecErrorKind, line: usize) -> Self {
+        Self { kind, line }
+    }
+
+    pub fn kind(&self) -> &SpecErrorKind {
+        &self.kind
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl Display for SpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            SpecErrorKind::MissingRules => write!(f, "missing `%%` before the rules"),
+            SpecErrorKind::InvalidDefinition => write!(f, "definition needs a name and a pattern"),
+            SpecErrorKind::UndefinedName(name) => write!(f, "undefined definition `{{{}}}`", name),
+            SpecErrorKind::UnclosedQuote => write!(f, "unclosed quote `\"`"),
+            SpecErrorKind::UnclosedCode => write!(f, "unclosed code block `%{{`"),
+            SpecErrorKind::UnclosedAction => write!(f, "unclosed action `{{`"),
+            SpecErrorKind::DanglingBar => write!(f, "`|` has no next rule to share its action"),
+            SpecErrorKind::Unsupported(what) => write!(f, "unsupported {}", what),
+            SpecErrorKind::Pattern(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for SpecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            SpecErrorKind::Pattern(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"%{
+use std::fmt;
+%}
+/* the numbers */
+DIGIT    [0-9]
+NUMBER   {DIGIT}+(\.{DIGIT}+)?
+ID       [a-z_][a-z0-9_]*
+%%
+"if"|"else"   { return Keyword; }
+{NUMBER}      return Number;
+{ID}          {
+    return Ident;
+}
+"=="          |
+"!="          return Cmp;
+[ \t\n]+      ;
+%%
+fn main() {}
+"#;
+
+    #[test]
+    fn sections() {
+        let spec = Spec::parse(SPEC).unwrap();
+
+        assert_eq!(spec.code(), "use std::fmt;\n");
+        assert_eq!(spec.definition("DIGIT"), Some("[0-9]"));
+        assert_eq!(
+            spec.definition("NUMBER"),
+            Some(r"(?:[0-9])+(\.(?:[0-9])+)?")
+        );
+        assert_eq!(spec.user_code(), "fn main() {}\n");
+
+        let rules: Vec<_> = spec
+            .rules()
+            .iter()
+            .map(|rule| (rule.pattern.as_str(), rule.action.as_str(), rule.line))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                ("(?:if)|(?:else)", "{ return Keyword; }", 9),
+                (r"(?:(?:[0-9])+(\.(?:[0-9])+)?)", "return Number;", 10),
+                ("(?:[a-z_][a-z0-9_]*)", "{\n    return Ident;\n}", 11),
+                ("(?:==)", "return Cmp;", 14),
+                ("(?:!=)", "return Cmp;", 15),
+                (r"[ \t\n]+", ";", 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexer() {
+        let lexer = Spec::parse(SPEC).unwrap().lexer().unwrap();
+
+        let tokens: Vec<_> = lexer
+            .tokens("if x1 != 3.25")
+            .map(|token| token.unwrap())
+            .filter(|token| token.kind != ";")
+            .map(|token| (token.kind, token.text))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                ("{ return Keyword; }".to_string(), "if"),
+                ("{\n    return Ident;\n}".to_string(), "x1"),
+                ("return Cmp;".to_string(), "!="),
+                ("return Number;".to_string(), "3.25"),
+            ]
+        );
+    }
+
+    #[test]
+    fn anchors_match_lines() {
+        let lexer = Spec::parse("%%\n^# C\na$ A\n.|\\n O\n")
+            .unwrap()
+            .lexer()
+            .unwrap();
+
+        let tokens: Vec<_> = lexer
+            .tokens("a\n#a#a")
+            .map(|token| token.unwrap())
+            .map(|token| (token.kind, token.text))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                ("A".to_string(), "a"),
+                ("O".to_string(), "\n"),
+                ("C".to_string(), "#"),
+                ("O".to_string(), "a"),
+                ("O".to_string(), "#"),
+                ("A".to_string(), "a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_are_literal() {
+        let spec = Spec::parse("%%\n\"a.b*\\n\"\\ c  x\n").unwrap();
+
+        assert_eq!(spec.rules()[0].pattern, r"(?:a\.b\*\n) c");
+        assert_eq!(spec.user_code(), "");
+    }
+
+    #[test]
+    fn escapes_in_quotes_are_literal() {
+        let spec = Spec::parse("%%\n\"a\\d\" d\n\"\\b\" b\n\"\\\"\\w+\" w\n").unwrap();
+
+        let patterns: Vec<_> = spec
+            .rules()
+            .iter()
+            .map(|rule| rule.pattern.as_str())
+            .collect();
+        assert_eq!(patterns, [r"(?:ad)", r"\x08", r#"(?:"w\+)"#]);
+
+        let lexer = spec.lexer().unwrap();
+        let tokens: Vec<_> = lexer
+            .tokens("ad\u{8}\"w+")
+            .map(|token| token.unwrap().kind)
+            .collect();
+        assert_eq!(tokens, ["d", "b", "w"]);
+        assert!(lexer.tokens("a5").next().unwrap().is_err());
+    }
+
+    #[test]
+    fn quantifier_takes_the_whole_quote() {
+        let spec = Spec::parse(
+            "%%
+\"ab\"+ x
+\"c\"+ y
+",
+        )
+        .unwrap();
+        assert_eq!(spec.rules()[0].pattern, "(?:ab)+");
+        assert_eq!(spec.rules()[1].pattern, "c+");
+
+        let lexer = spec.lexer().unwrap();
+        let tokens: Vec<_> = lexer
+            .tokens("ababcc")
+            .map(|token| token.unwrap().text)
+            .collect();
+        assert_eq!(tokens, ["abab", "cc"]);
+    }
+
+    #[test]
+    fn definition_takes_the_whole_line() {
+        let spec = Spec::parse("KW  if|else  then \t\n%%\n{KW} x\n").unwrap();
+
+        assert_eq!(spec.definition("KW"), Some("if|else  then"));
+        assert_eq!(spec.rules()[0].pattern, "(?:if|else  then)");
+    }
+
+    #[test]
+    fn action_with_lifetimes_and_chars() {
+        let spec =
+            Spec::parse("%%\na { let s: &'static str = \"x\"; }\nb { '}' }\nc { f::<'a>('{') }\n")
+                .unwrap();
+
+        let actions: Vec<_> = spec
+            .rules()
+            .iter()
+            .map(|rule| rule.action.as_str())
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                "{ let s: &'static str = \"x\"; }",
+                "{ '}' }",
+                "{ f::<'a>('{') }"
+            ]
+        );
+    }
+
+    #[test]
+    fn brackets_keep_whitespace_and_braces() {
+        let spec = Spec::parse("%%\n[] {}]+ x\n[^]a]{2} y\n").unwrap();
+
+        assert_eq!(spec.rules()[0].pattern, "[] {}]+");
+        assert_eq!(spec.rules()[1].pattern, "[^]a]{2}");
+    }
+
+    #[test]
+    fn errors_with_lines() {
+        let cases = [
+            ("A [a]\n", SpecErrorKind::MissingRules, 2),
+            ("A\n%%\n", SpecErrorKind::InvalidDefinition, 1),
+            (
+                "%%\na x\n{B}+ y\n",
+                SpecErrorKind::UndefinedName("B".to_string()),
+                3,
+            ),
+            ("%%\n\"ab x\n", SpecErrorKind::UnclosedQuote, 2),
+            ("%{\ncode\n%%\n", SpecErrorKind::UnclosedCode, 1),
+            ("%%\na {\nx;\n", SpecErrorKind::UnclosedAction, 2),
+            ("%%\na |\n", SpecErrorKind::DanglingBar, 2),
+            (
+                "%option noyywrap\n%%\n",
+                SpecErrorKind::Unsupported("%option".to_string()),
+                1,
+            ),
+            (
+                "%%\na/b x\n",
+                SpecErrorKind::Unsupported("trailing context `/`".to_string()),
+                2,
+            ),
+        ];
+
+        for (source, kind, line) in cases {
+            let err = Spec::parse(source).unwrap_err();
+            assert_eq!((err.kind(), err.line()), (&kind, line), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn pattern_error_has_the_line() {
+        let err = Spec::parse("%%\na x\n\n(b y\n")
+            .unwrap()
+            .lexer()
+            .unwrap_err();
+
+        assert_eq!(err.line(), 4);
+        assert!(matches!(err.kind(), SpecErrorKind::Pattern(_)));
+        assert!(err.to_string().starts_with("line 4: unclosed parenthesis"));
+    }
+}
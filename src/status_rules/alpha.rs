@@ -14,9 +14,9 @@ impl StatusTargetRule for AlphaRule {
     fn input(&self, text: char) -> bool {
         text.is_ascii_alphabetic()
     }
-    
-    fn get_id(&self) -> usize {
-        1_003
+
+    fn intervals(&self) -> Vec<(char, char)> {
+        vec![('A', 'Z'), ('a', 'z')]
     }
     fn clone(&self) -> Box<dyn StatusTargetRule> {
         Box::new(Self)
@@ -40,7 +40,7 @@ mod tests {
         const EXPECTED: char = '0';
         let rule = AlphaRule::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 
     #[test]
@@ -48,6 +48,6 @@ mod tests {
         const EXPECTED: char = '_';
         let rule = AlphaRule::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 }
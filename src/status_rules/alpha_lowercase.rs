@@ -14,9 +14,9 @@ impl StatusTargetRule for AlphaLowercaseRule {
     fn input(&self, text: char) -> bool {
         text.is_ascii_lowercase()
     }
-    
-    fn get_id(&self) -> usize {
-        1_005
+
+    fn intervals(&self) -> Vec<(char, char)> {
+        vec![('a', 'z')]
     }
     fn clone(&self) -> Box<dyn StatusTargetRule> {
         Box::new(Self)
@@ -40,7 +40,7 @@ mod tests {
         const EXPECTED: char = 'A';
         let rule = AlphaLowercaseRule::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 
     #[test]
@@ -48,7 +48,7 @@ mod tests {
         const EXPECTED: char = '0';
         let rule = AlphaLowercaseRule::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 
     #[test]
@@ -56,6 +56,6 @@ mod tests {
         const EXPECTED: char = '_';
         let rule = AlphaLowercaseRule::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 }
@@ -14,9 +14,9 @@ impl StatusTargetRule for AlphaUppercaseRule {
     fn input(&self, text: char) -> bool {
         text.is_ascii_uppercase()
     }
-    
-    fn get_id(&self) -> usize {
-        1_004
+
+    fn intervals(&self) -> Vec<(char, char)> {
+        vec![('A', 'Z')]
     }
     fn clone(&self) -> Box<dyn StatusTargetRule> {
         Box::new(Self)
@@ -32,7 +32,7 @@ mod tests {
         const EXPECTED: char = 'a';
         let rule = AlphaUppercaseRule::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 
     #[test]
@@ -40,7 +40,7 @@ mod tests {
         const EXPECTED: char = 'A';
         let rule = AlphaUppercaseRule::boxed();
 
-        assert_eq!(rule.input(EXPECTED), true);
+        assert!(rule.input(EXPECTED));
     }
 
     #[test]
@@ -48,7 +48,7 @@ mod tests {
         const EXPECTED: char = '0';
         let rule = AlphaUppercaseRule::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 
     #[test]
@@ -56,6 +56,6 @@ mod tests {
         const EXPECTED: char = '_';
         let rule = AlphaUppercaseRule::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 }
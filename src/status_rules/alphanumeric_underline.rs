@@ -6,6 +6,8 @@ use super::StatusTargetRule;
 pub struct AlphanumericUnderlineRule;
 
 impl AlphanumericUnderlineRule {
+    pub const RANGES: &'static [(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+
     pub fn boxed() -> Box<Self> {
         Box::new(Self)
     }
@@ -16,8 +18,8 @@ impl StatusTargetRule for AlphanumericUnderlineRule {
         text.is_ascii_alphanumeric() || text == '_'
     }
 
-    fn get_id(&self) -> usize {
-        1_002
+    fn intervals(&self) -> Vec<(char, char)> {
+        Self::RANGES.to_vec()
     }
     fn clone(&self) -> Box<dyn StatusTargetRule> {
         Box::new(Self)
@@ -58,7 +60,7 @@ mod tests {
         let rule = AlphanumericUnderlineRule::boxed();
 
         for item in EXPECTED {
-            assert_eq!(rule.input(item), false);
+            assert!(!rule.input(item));
         }
     }
 }
@@ -0,0 +1,74 @@
+use super::StatusTargetRule;
+
+/// match any single character, `.`,
+/// `\n` is only matched if `new_line` is set
+#[derive(Clone, PartialEq, Eq)]
+pub struct AnyCharRule {
+    new_line: bool,
+}
+
+impl AnyCharRule {
+    pub fn new(new_line: bool) -> Self {
+        Self { new_line }
+    }
+
+    pub fn boxed(new_line: bool) -> Box<Self> {
+        Box::new(Self::new(new_line))
+    }
+}
+
+impl StatusTargetRule for AnyCharRule {
+    fn input(&self, text: char) -> bool {
+        match text {
+            '\n' => self.new_line,
+            _ => true,
+        }
+    }
+
+    fn intervals(&self) -> Vec<(char, char)> {
+        if self.new_line {
+            vec![('\0', char::MAX)]
+        } else {
+            vec![('\0', '\u{9}'), ('\u{B}', char::MAX)]
+        }
+    }
+
+    fn clone(&self) -> Box<dyn StatusTargetRule> {
+        Box::new(Clone::clone(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_char() {
+        const EXPECTED: [char; 6] = ['a', '0', '_', ' ', '\r', '一'];
+        let rule = AnyCharRule::boxed(false);
+
+        for item in EXPECTED {
+            assert!(rule.input(item));
+        }
+    }
+
+    #[test]
+    fn new_line() {
+        assert!(!AnyCharRule::boxed(false).input('\n'));
+        assert!(AnyCharRule::boxed(true).input('\n'));
+    }
+
+    #[test]
+    fn nul() {
+        assert!(AnyCharRule::boxed(false).input('\0'));
+    }
+
+    #[test]
+    fn intervals() {
+        assert_eq!(
+            AnyCharRule::boxed(false).intervals(),
+            [('\0', '\u{9}'), ('\u{B}', char::MAX)]
+        );
+        assert_eq!(AnyCharRule::boxed(true).intervals(), [('\0', char::MAX)]);
+    }
+}
@@ -6,6 +6,8 @@ use super::StatusTargetRule;
 pub struct Digit;
 
 impl Digit {
+    pub const RANGES: &'static [(char, char)] = &[('0', '9')];
+
     pub fn boxed() -> Box<Self> {
         Box::new(Self)
     }
@@ -15,9 +17,9 @@ impl StatusTargetRule for Digit {
     fn input(&self, text: char) -> bool {
         text.is_ascii_digit()
     }
-    
-    fn get_id(&self) -> usize {
-        1_001
+
+    fn intervals(&self) -> Vec<(char, char)> {
+        Self::RANGES.to_vec()
     }
 
     fn clone(&self) -> Box<dyn StatusTargetRule> {
@@ -34,7 +36,7 @@ mod tests {
         const EXPECTED: char = 'a';
         let rule = Digit::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 
     #[test]
@@ -42,7 +44,7 @@ mod tests {
         const EXPECTED: char = 'A';
         let rule = Digit::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 
     #[test]
@@ -50,7 +52,7 @@ mod tests {
         const EXPECTED: char = '0';
         let rule = Digit::boxed();
 
-        assert_eq!(rule.input(EXPECTED), true);
+        assert!(rule.input(EXPECTED));
     }
 
     #[test]
@@ -58,6 +60,6 @@ mod tests {
         const EXPECTED: char = '_';
         let rule = Digit::boxed();
 
-        assert_eq!(rule.input(EXPECTED), false);
+        assert!(!rule.input(EXPECTED));
     }
 }
@@ -0,0 +1,231 @@
+use std::fmt::Display;
+
+use super::StatusTargetRule;
+use crate::ast::{Ast, Class, ClassItem};
+
+/// match a single character within any of the ranges,
+/// a whole class like [a-f0-9_] is kept as the sorted ranges [0-9], [_], [a-f]
+///
+/// the ranges are canonical, so two classes of the same characters are equal,
+/// which makes it the identity of every rule, see `StatusTargetRule::class`
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct IntervalSetRule {
+    /// sorted, neither overlapping nor adjacent
+    ranges: Vec<(char, char)>,
+}
+
+impl IntervalSetRule {
+    /// creates the rule from ranges in any order, ranges whose start is greater than end are ignored
+    pub fn new(ranges: impl IntoIterator<Item = (char, char)>) -> Self {
+        let mut sorted: Vec<_> = ranges.into_iter().filter(|(from, to)| from <= to).collect();
+        sorted.sort_unstable();
+
+        let mut merged: Vec<(char, char)> = Vec::with_capacity(sorted.len());
+        for (from, to) in sorted {
+            match merged.last_mut() {
+                Some(last) if next_char(last.1).is_none_or(|next| from <= next) => {
+                    last.1 = last.1.max(to);
+                }
+                _ => merged.push((from, to)),
+            }
+        }
+
+        Self { ranges: merged }
+    }
+
+    pub fn boxed(ranges: impl IntoIterator<Item = (char, char)>) -> Box<Self> {
+        Box::new(Self::new(ranges))
+    }
+
+    /// the rule matching every character this rule does not
+    pub fn negate(&self) -> Self {
+        let mut ranges = Vec::with_capacity(self.ranges.len() + 1);
+        let mut from = Some('\0');
+
+        for &(start, end) in self.ranges.iter() {
+            let Some(cur) = from else {
+                break;
+            };
+            if cur < start {
+                ranges.push((cur, prev_char(start).unwrap()));
+            }
+            from = next_char(end);
+        }
+
+        if let Some(cur) = from {
+            ranges.push((cur, char::MAX));
+        }
+
+        Self { ranges }
+    }
+
+    pub fn ranges(&self) -> &[(char, char)] {
+        &self.ranges
+    }
+}
+
+/// the character right after `c`, skipping the surrogates which are not characters
+pub(crate) fn next_char(c: char) -> Option<char> {
+    match c {
+        '\u{D7FF}' => Some('\u{E000}'),
+        c => char::from_u32(c as u32 + 1),
+    }
+}
+
+/// the character right before `c`, skipping the surrogates which are not characters
+pub(crate) fn prev_char(c: char) -> Option<char> {
+    match c {
+        '\0' => None,
+        '\u{E000}' => Some('\u{D7FF}'),
+        c => char::from_u32(c as u32 - 1),
+    }
+}
+
+impl StatusTargetRule for IntervalSetRule {
+    fn input(&self, text: char) -> bool {
+        self.ranges
+            .binary_search_by(|&(from, to)| {
+                if to < text {
+                    std::cmp::Ordering::Less
+                } else if from > text {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    fn intervals(&self) -> Vec<(char, char)> {
+        self.ranges.clone()
+    }
+
+    fn clone(&self) -> Box<dyn StatusTargetRule> {
+        Box::new(Clone::clone(self))
+    }
+
+    fn class(&self) -> IntervalSetRule {
+        Clone::clone(self)
+    }
+}
+
+/// printed as a pattern matching the same characters, like `a`, `[0-9a-f]`,
+/// or negated like `[^\n]` if it takes fewer ranges
+impl Display for IntervalSetRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = |ranges: &[(char, char)]| {
+            ranges
+                .iter()
+                .map(|&(from, to)| match from == to {
+                    true => ClassItem::Char(from),
+                    false => ClassItem::Range(from, to),
+                })
+                .collect()
+        };
+
+        let negated = self.negate();
+        let ast = match self.ranges[..] {
+            [(from, to)] if from == to => Ast::Literal(from),
+            _ if negated.ranges.len() < self.ranges.len() || self.ranges.is_empty() => {
+                Ast::Class(Class::Bracket {
+                    negated: true,
+                    items: items(&negated.ranges),
+                })
+            }
+            _ => Ast::Class(Class::Bracket {
+                negated: false,
+                items: items(&self.ranges),
+            }),
+        };
+
+        write!(f, "{}", ast)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_overlapping_and_adjacent() {
+        let rule =
+            IntervalSetRule::new([('x', 'z'), ('a', 'c'), ('b', 'f'), ('g', 'g'), ('0', '9')]);
+
+        assert_eq!(rule.ranges, [('0', '9'), ('a', 'g'), ('x', 'z')]);
+    }
+
+    #[test]
+    fn ignore_reversed_range() {
+        let rule = IntervalSetRule::new([('z', 'a')]);
+
+        assert!(rule.ranges.is_empty());
+        assert!(!rule.input('m'));
+    }
+
+    #[test]
+    fn input() {
+        let rule = IntervalSetRule::new([('a', 'f'), ('0', '9'), ('_', '_')]);
+
+        for c in ['a', 'c', 'f', '0', '9', '_'] {
+            assert!(rule.input(c));
+        }
+        for c in ['g', 'A', '-', ' ', '一'] {
+            assert!(!rule.input(c));
+        }
+    }
+
+    #[test]
+    fn unicode_range() {
+        let rule = IntervalSetRule::new([('α', 'ω')]);
+
+        assert!(rule.input('β'));
+        assert!(!rule.input('a'));
+    }
+
+    #[test]
+    fn negate() {
+        let rule = IntervalSetRule::new([('0', '9')]).negate();
+
+        assert_eq!(rule.ranges, [('\0', '/'), (':', char::MAX)]);
+        assert!(!rule.input('5'));
+        assert!(rule.input('a'));
+        assert!(rule.input('一'));
+    }
+
+    #[test]
+    fn negate_edges() {
+        let rule = IntervalSetRule::new([('\0', 'a'), ('z', char::MAX)]).negate();
+        assert_eq!(rule.ranges, [('b', 'y')]);
+
+        let rule = IntervalSetRule::new([]).negate();
+        assert_eq!(rule.ranges, [('\0', char::MAX)]);
+        assert!(rule.negate().ranges.is_empty());
+    }
+
+    #[test]
+    fn display() {
+        let cases = [
+            (IntervalSetRule::new([('a', 'a')]), "a"),
+            (IntervalSetRule::new([('*', '*')]), r"\*"),
+            (
+                IntervalSetRule::new([('a', 'f'), ('0', '9'), ('-', '-')]),
+                r"[\-0-9a-f]",
+            ),
+            (IntervalSetRule::new([('\n', '\n')]).negate(), r"[^\n]"),
+            (IntervalSetRule::new([]), "[^\\x00-\u{10FFFF}]"),
+        ];
+
+        for (rule, expected) in cases {
+            assert_eq!(rule.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn surrogates_are_adjacent() {
+        let rule = IntervalSetRule::new([('\u{E000}', '\u{E010}'), ('a', '\u{D7FF}')]);
+        assert_eq!(rule.ranges, [('a', '\u{E010}')]);
+
+        let rule = IntervalSetRule::new([('\u{E000}', char::MAX)]).negate();
+        assert_eq!(rule.ranges, [('\0', '\u{D7FF}')]);
+    }
+}
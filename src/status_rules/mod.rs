@@ -1,25 +1,128 @@
+use std::{
+    cmp::Ordering,
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+};
+
 mod alpha;
 mod alpha_lowercase;
 mod alpha_uppercase;
 mod alphanumeric_underline;
+mod any_char;
 mod digit;
+mod interval_set;
+mod negated;
 mod single_char;
+mod whitespace;
 
 pub use alpha::AlphaRule;
 pub use alpha_lowercase::AlphaLowercaseRule;
 pub use alpha_uppercase::AlphaUppercaseRule;
 pub use alphanumeric_underline::AlphanumericUnderlineRule;
+pub use any_char::AnyCharRule;
 pub use digit::Digit;
+pub use interval_set::IntervalSetRule;
+pub(crate) use interval_set::{next_char, prev_char};
+pub use negated::NegatedRule;
 pub use single_char::SingleCharRule;
+pub use whitespace::WhitespaceRule;
 
-pub trait StatusTargetRule {
-    fn get_id(&self) -> usize;
+pub trait StatusTargetRule: Send + Sync {
     fn input(&self, text: char) -> bool;
+    /// the characters `input` accepts, as sorted ranges,
+    /// they split the characters into the classes of DFA
+    fn intervals(&self) -> Vec<(char, char)>;
     fn clone(&self) -> Box<dyn StatusTargetRule>;
+
+    /// the canonical class of the characters the rule accepts,
+    /// the rules accepting the same characters have the same class, whatever their types are
+    fn class(&self) -> IntervalSetRule {
+        IntervalSetRule::new(self.intervals())
+    }
 }
 
-impl PartialEq for dyn StatusTargetRule {
+/// the rules are compared by the characters they accept, see `StatusTargetRule::class`
+impl PartialEq for dyn StatusTargetRule + '_ {
     fn eq(&self, other: &Self) -> bool {
-        self.get_id() == other.get_id()
+        self.class() == other.class()
+    }
+}
+
+impl Eq for dyn StatusTargetRule + '_ {}
+
+impl Hash for dyn StatusTargetRule + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.class().hash(state);
+    }
+}
+
+impl PartialOrd for dyn StatusTargetRule + '_ {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for dyn StatusTargetRule + '_ {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.class().cmp(&other.class())
+    }
+}
+
+impl Display for dyn StatusTargetRule + '_ {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.class())
+    }
+}
+
+impl Debug for dyn StatusTargetRule + '_ {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.class())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn rules_are_compared_by_characters() {
+        let a: Box<dyn StatusTargetRule> = SingleCharRule::boxed('a');
+        let b: Box<dyn StatusTargetRule> = SingleCharRule::boxed('b');
+        assert!(*a != *b);
+        assert!(*a < *b);
+
+        let alpha: Box<dyn StatusTargetRule> = AlphaRule::boxed();
+        let ranges: Box<dyn StatusTargetRule> = IntervalSetRule::boxed([('a', 'z'), ('A', 'Z')]);
+        assert!(*alpha == *ranges);
+
+        let not_digit: Box<dyn StatusTargetRule> = NegatedRule::boxed(Digit::boxed());
+        let negated: Box<dyn StatusTargetRule> =
+            Box::new(IntervalSetRule::new(Digit::RANGES.iter().copied()).negate());
+        assert!(*not_digit == *negated);
+        assert!(*not_digit != *alpha);
+    }
+
+    #[test]
+    fn rules_are_deduplicated() {
+        let rules: Vec<Box<dyn StatusTargetRule>> = vec![
+            SingleCharRule::boxed('a'),
+            IntervalSetRule::boxed([('a', 'a')]),
+            SingleCharRule::boxed('b'),
+            AlphanumericUnderlineRule::boxed(),
+        ];
+
+        let set: HashSet<_> = rules.iter().map(|rule| rule.as_ref()).collect();
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(SingleCharRule::boxed('a').class().to_string(), "a");
+        let word: Box<dyn StatusTargetRule> = AlphanumericUnderlineRule::boxed();
+        assert_eq!(word.to_string(), "[0-9A-Z_a-z]");
+        let any: Box<dyn StatusTargetRule> = AnyCharRule::boxed(false);
+        assert_eq!(any.to_string(), r"[^\n]");
     }
 }
@@ -0,0 +1,75 @@
+use super::{IntervalSetRule, StatusTargetRule};
+
+/// match single character which the inner rule does not match,
+/// like \D, \S, \W
+pub struct NegatedRule {
+    rule: Box<dyn StatusTargetRule>,
+}
+
+impl NegatedRule {
+    pub fn new(rule: Box<dyn StatusTargetRule>) -> Self {
+        Self { rule }
+    }
+
+    pub fn boxed(rule: Box<dyn StatusTargetRule>) -> Box<Self> {
+        Box::new(Self::new(rule))
+    }
+}
+
+impl StatusTargetRule for NegatedRule {
+    fn input(&self, text: char) -> bool {
+        !self.rule.input(text)
+    }
+
+    fn intervals(&self) -> Vec<(char, char)> {
+        IntervalSetRule::new(self.rule.intervals())
+            .negate()
+            .ranges()
+            .to_vec()
+    }
+
+    fn clone(&self) -> Box<dyn StatusTargetRule> {
+        Box::new(Self {
+            rule: self.rule.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_rules::{AlphanumericUnderlineRule, Digit};
+
+    #[test]
+    fn not_digit() {
+        let rule = NegatedRule::boxed(Digit::boxed());
+
+        assert!(rule.input('a'));
+        assert!(rule.input('_'));
+        assert!(!rule.input('0'));
+    }
+
+    #[test]
+    fn not_word() {
+        let rule = NegatedRule::boxed(AlphanumericUnderlineRule::boxed());
+
+        assert!(rule.input('-'));
+        assert!(rule.input('一'));
+        assert!(!rule.input('a'));
+        assert!(!rule.input('_'));
+    }
+
+    #[test]
+    fn nul() {
+        let rule = NegatedRule::boxed(Digit::boxed());
+
+        assert!(rule.input('\0'));
+    }
+
+    #[test]
+    fn intervals() {
+        let rule = NegatedRule::boxed(Digit::boxed());
+
+        assert_eq!(rule.intervals(), [('\0', '/'), (':', char::MAX)]);
+    }
+}
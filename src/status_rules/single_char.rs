@@ -16,8 +16,8 @@ impl SingleCharRule {
 }
 
 impl StatusTargetRule for SingleCharRule {
-    fn get_id(&self) -> usize {
-        1_000
+    fn intervals(&self) -> Vec<(char, char)> {
+        vec![(self.text, self.text)]
     }
     fn input(&self, text: char) -> bool {
         self.text == text
@@ -53,7 +53,7 @@ mod tests {
         const UNEXPECTED_TEXT: char = 'b';
         let rule = SingleCharRule::new(EXPECTED_RULE);
 
-        assert_ne!(rule.input(UNEXPECTED_TEXT), true);
+        assert!(!rule.input(UNEXPECTED_TEXT));
     }
 
     #[test]
@@ -62,6 +62,6 @@ mod tests {
         const UNEXPECTED_TEXT: char = ' ';
         let rule = SingleCharRule::new(EXPECTED_RULE);
 
-        assert_ne!(rule.input(UNEXPECTED_TEXT), true);
+        assert!(!rule.input(UNEXPECTED_TEXT));
     }
 }
@@ -0,0 +1,53 @@
+use super::StatusTargetRule;
+
+/// match single whitespace,
+/// equal to [ \t\n\x0B\x0C\r]
+#[derive(Clone, PartialEq, Eq)]
+pub struct WhitespaceRule;
+
+impl WhitespaceRule {
+    pub const RANGES: &'static [(char, char)] = &[('\t', '\r'), (' ', ' ')];
+
+    pub fn boxed() -> Box<Self> {
+        Box::new(Self)
+    }
+}
+
+impl StatusTargetRule for WhitespaceRule {
+    fn input(&self, text: char) -> bool {
+        matches!(text, ' ' | '\t'..='\r')
+    }
+
+    fn intervals(&self) -> Vec<(char, char)> {
+        Self::RANGES.to_vec()
+    }
+
+    fn clone(&self) -> Box<dyn StatusTargetRule> {
+        Box::new(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace() {
+        const EXPECTED: [char; 6] = [' ', '\t', '\n', '\x0B', '\x0C', '\r'];
+        let rule = WhitespaceRule::boxed();
+
+        for item in EXPECTED {
+            assert!(rule.input(item));
+        }
+    }
+
+    #[test]
+    fn not_whitespace() {
+        const EXPECTED: [char; 4] = ['a', '0', '_', '\0'];
+        let rule = WhitespaceRule::boxed();
+
+        for item in EXPECTED {
+            assert!(!rule.input(item));
+        }
+    }
+}
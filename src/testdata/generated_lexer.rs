@@ -0,0 +1,130 @@
+// generated by reglex, do not edit
+
+/// sorted ranges of characters, all the characters of a class go to the same status
+static CLASSES: [(char, char); 21] = [
+    ('\0', '\u{8}'),
+    ('\t', '\t'),
+    ('\n', '\n'),
+    ('\u{b}', '\r'),
+    ('\u{e}', '\u{1f}'),
+    (' ', ' '),
+    ('!', '/'),
+    ('0', '9'),
+    (':', '<'),
+    ('=', '='),
+    ('>', '@'),
+    ('A', 'Z'),
+    ('[', '^'),
+    ('_', '_'),
+    ('`', '`'),
+    ('a', 'e'),
+    ('f', 'f'),
+    ('g', 'h'),
+    ('i', 'i'),
+    ('j', 'z'),
+    ('{', '\u{10ffff}'),
+];
+
+/// no status, or no rule
+const NONE: u32 = u32::MAX;
+
+/// the next status of each status through each class
+static NEXT: [[u32; 21]; 9] = [
+    [NONE, 2, 2, 2, NONE, 2, NONE, 3, NONE, 4, NONE, NONE, NONE, NONE, NONE, 5, 5, 5, 6, 5, NONE],
+    [NONE, 2, 2, 2, NONE, 2, NONE, 3, NONE, 4, NONE, NONE, NONE, NONE, NONE, 5, 5, 5, 5, 5, NONE],
+    [NONE, 2, 2, 2, NONE, 2, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
+    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, 3, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
+    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, 7, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
+    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, 5, 5, 5, 5, 5, NONE],
+    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, 5, 8, 5, 5, 5, NONE],
+    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
+    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, 5, 5, 5, 5, 5, NONE],
+];
+
+/// the rule each status accepts right before a character of each class, and at the end of the input last
+static ACCEPT: [[u32; 22]; 9] = [
+    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
+    [NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE],
+    [4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4],
+    [2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2],
+    [3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3],
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+    [3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3],
+    [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 1, 0, 1, 1, 1, 1, 1, 0, 0],
+];
+
+/// the start after a character of each class, the status 0 is the start at the beginning of the input
+static STARTS: [u32; 21] = [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 1, 0, 1, 1, 1, 1, 1, 0];
+
+/// the token kind of every rule
+pub static KINDS: [&str; 5] = [
+    "If",
+    "Ident",
+    "Number",
+    "Op",
+    "Space",
+];
+
+/// a token of the input, the span is in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// the index of the rule
+    pub rule: usize,
+    pub kind: &'static str,
+    pub span: core::ops::Range<usize>,
+    pub text: &'a str,
+}
+
+/// the index of the class containing `c`, the classes cover every character
+fn class_of(c: char) -> usize {
+    CLASSES
+        .binary_search_by(|&(from, to)| {
+            if to < c {
+                core::cmp::Ordering::Less
+            } else if from > c {
+                core::cmp::Ordering::Greater
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        })
+        .unwrap_or(0)
+}
+
+/// the longest token at the byte offset `at`, the earliest rule if several match it,
+/// `None` if no rule matches a non empty text there
+pub fn next_token(input: &str, at: usize) -> Option<Token<'_>> {
+    let mut current = match input[..at].chars().next_back() {
+        Some(c) => STARTS[class_of(c)] as usize,
+        None => 0,
+    };
+    let mut longest = None;
+
+    for (i, c) in input[at..].char_indices() {
+        let class = class_of(c);
+        if ACCEPT[current][class] != NONE {
+            longest = Some((at + i, ACCEPT[current][class]));
+        }
+
+        match NEXT[current][class] {
+            NONE => return token(input, at, longest),
+            next => current = next as usize,
+        }
+    }
+
+    if ACCEPT[current][CLASSES.len()] != NONE {
+        longest = Some((input.len(), ACCEPT[current][CLASSES.len()]));
+    }
+    token(input, at, longest)
+}
+
+fn token(input: &str, at: usize, longest: Option<(usize, u32)>) -> Option<Token<'_>> {
+    let (end, rule) = longest.filter(|&(end, _)| end > at)?;
+
+    Some(Token {
+        rule: rule as usize,
+        kind: KINDS[rule as usize],
+        span: at..end,
+        text: &input[at..end],
+    })
+}
@@ -0,0 +1,198 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+fn reglex(args: &[&str], stdin: impl AsRef<[u8]>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_reglex"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_ref())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+/// a file in the temporary directory, unique to the test, deleted on drop
+struct TempFile(PathBuf);
+
+impl TempFile {
+    /// the path only, the file is not created
+    fn new(name: &str) -> Self {
+        Self(env::temp_dir().join(format!("reglex-{}-{}", std::process::id(), name)))
+    }
+
+    fn with(name: &str, content: &str) -> Self {
+        let file = Self::new(name);
+        fs::write(&file.0, content).unwrap();
+        file
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn match_prints_every_match() {
+    let output = reglex(&["match", "a+", "xaay a"], "");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "1..3: aa\n5..6: a\n");
+}
+
+#[test]
+fn match_nothing_exits_with_1() {
+    let output = reglex(&["match", "b", "aaa"], "");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn wrong_pattern_exits_with_2() {
+    let output = reglex(&["match", "(a", "a"], "");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stderr(&output), "unclosed parenthesis `(` at 0\n(a\n^\n");
+}
+
+#[test]
+fn wrong_arguments_exit_with_2() {
+    for args in [
+        &[][..],
+        &["match", "a"],
+        &["gen", "a.l", "-x", "b"],
+        &["what"],
+    ] {
+        let output = reglex(args, "");
+
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(stderr(&output).starts_with("usage:"), "{:?}", args);
+    }
+}
+
+#[test]
+fn grep_stdin() {
+    let output = reglex(&["grep", "o$"], "one\ntwo\nthree\nzoo\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "two\nzoo\n");
+
+    let output = reglex(&["grep", "x"], "one\ntwo\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn grep_lines_of_wrong_utf8() {
+    let output = reglex(&["grep", "b"], b"a\xFF\nb\xFE\r\nc\nab");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "b\u{FFFD}\nab\n");
+}
+
+#[test]
+fn grep_files() {
+    let first = TempFile::with("grep-first.txt", "apple\nbanana\n");
+    let second = TempFile::with("grep-second.txt", "cherry\navocado\n");
+    let (first, second) = (first.path(), second.path());
+
+    let output = reglex(&["grep", "^a", first], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "apple\n");
+
+    // prefixed by the file if there are several
+    let output = reglex(&["grep", "^a", first, second], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        format!("{}:apple\n{}:avocado\n", first, second)
+    );
+
+    let missing = TempFile::new("grep-missing.txt");
+    let output = reglex(&["grep", "a", missing.path()], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with(missing.path()));
+}
+
+#[test]
+fn gen_prints_or_writes_the_lexer() {
+    let spec = TempFile::with("gen.l", "%%\n[0-9]+ Number\n[a-z]+ Ident\n");
+    let spec = spec.path();
+
+    let output = reglex(&["gen", spec], "");
+    assert_eq!(output.status.code(), Some(0));
+    let code = stdout(&output);
+    assert!(code.starts_with("// generated by reglex, do not edit\n"));
+    assert!(code.contains("pub static KINDS: [&str; 2] = [\n    \"Number\",\n    \"Ident\",\n];"));
+    assert!(code.contains("pub fn next_token(input: &str, at: usize) -> Option<Token<'_>>"));
+
+    let lexer = TempFile::new("lexer.rs");
+    let output = reglex(&["gen", spec, "-o", lexer.path()], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+    assert_eq!(fs::read_to_string(lexer.path()).unwrap(), code);
+}
+
+#[test]
+fn gen_wrong_spec_exits_with_2() {
+    let spec = TempFile::with("gen-wrong.l", "%%\na x\n(b y\n");
+    let spec = spec.path();
+
+    let output = reglex(&["gen", spec], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with(&format!("{}: line 3: unclosed parenthesis", spec)));
+}
+
+#[test]
+fn dot_prints_the_nfa_or_the_dfa() {
+    let output = reglex(&["dot", "a|b"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("digraph NFA {\n"));
+    assert!(stdout(&output).ends_with("}\n"));
+
+    let output = reglex(&["dot", "a|b", "--dfa"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("digraph DFA {\n"));
+    assert!(stdout(&output).ends_with("}\n"));
+}
+
+#[test]
+fn dot_wrong_pattern_exits_with_2() {
+    for args in [&["dot", "(a"][..], &["dot", "(a", "--dfa"]] {
+        let output = reglex(args, "");
+
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert_eq!(stdout(&output), "", "{:?}", args);
+        assert_eq!(
+            stderr(&output),
+            "unclosed parenthesis `(` at 0\n(a\n^\n",
+            "{:?}",
+            args
+        );
+    }
+}